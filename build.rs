@@ -0,0 +1,55 @@
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=BUILD_TIMESTAMP");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=src");
+
+    let git_commit = std::env::var("GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+                .map(|commit| commit.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let build_timestamp = std::env::var("BUILD_TIMESTAMP")
+        .ok()
+        .filter(|timestamp| !timestamp.is_empty())
+        .unwrap_or_else(|| jiff::Timestamp::now().to_string());
+
+    println!("cargo:rustc-env=GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", build_timestamp);
+    println!(
+        "cargo:rustc-env=SPIDER_VERSION={}",
+        locked_version("spider")
+    );
+}
+
+/// Looks up the version of `name` resolved in `Cargo.lock`.
+fn locked_version(name: &str) -> String {
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    let mut lines = lock.lines();
+    let package = format!("name = \"{}\"", name);
+
+    while let Some(line) = lines.next() {
+        if line == package
+            && let Some(version) = lines
+                .next()
+                .and_then(|line| line.strip_prefix("version = \""))
+                .and_then(|line| line.strip_suffix('"'))
+        {
+            return version.to_string();
+        }
+    }
+
+    "unknown".to_string()
+}
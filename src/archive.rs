@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// A freshly crawled page as written to the archive.
+#[derive(Serialize)]
+pub struct ArchiveRecord {
+    pub url: String,
+    pub crawled_at: String,
+    pub status: Option<u16>,
+    pub content: String,
+}
+
+/// Handle to the background task appending crawl results to a JSON lines file.
+#[derive(Clone)]
+pub struct Archive {
+    tx: mpsc::Sender<ArchiveRecord>,
+}
+
+impl Archive {
+    /// Opens (or creates) the archive at `path` and spawns its writer task. The
+    /// file is rotated once it grows beyond `max_bytes`.
+    pub async fn spawn(path: PathBuf, max_bytes: u64) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create archive directory {:?}", parent))?;
+        }
+        let file = open(&path).await?;
+        let size = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let writer = Writer {
+            path,
+            max_bytes,
+            file,
+            size,
+        };
+        tokio::spawn(writer.run(rx));
+
+        Ok(Archive { tx })
+    }
+
+    /// Queues a record without waiting. Records are dropped when the writer
+    /// cannot keep up, so archiving never adds latency to a crawl.
+    pub fn record(&self, record: ArchiveRecord) {
+        if let Err(e) = self.tx.try_send(record) {
+            warn!("Dropping archive record: {}", e);
+        }
+    }
+}
+
+async fn open(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open archive {:?}", path))
+}
+
+struct Writer {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    size: u64,
+}
+
+impl Writer {
+    async fn run(mut self, mut rx: mpsc::Receiver<ArchiveRecord>) {
+        while let Some(record) = rx.recv().await {
+            if let Err(e) = self.write(&record).await {
+                error!("Failed to archive {}: {}", record.url, e);
+            }
+        }
+    }
+
+    async fn write(&mut self, record: &ArchiveRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate().await?;
+        }
+
+        self.file.write_all(&line).await?;
+        self.file.flush().await?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Renames the current file to `<path>.<timestamp>` and starts a new one.
+    async fn rotate(&mut self) -> Result<()> {
+        let timestamp = jiff::Timestamp::now().strftime("%Y%m%dT%H%M%SZ");
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{}", timestamp));
+
+        fs::rename(&self.path, &rotated)
+            .await
+            .context("Failed to rotate archive")?;
+        info!("Rotated archive to {:?}", rotated);
+
+        self.file = open(&self.path).await?;
+        self.size = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result, anyhow};
+use log::warn;
+use reqwest::StatusCode;
+use serde::Serialize;
+use spider::url::Url;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Pause before the first retry, doubled for every further one.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Longest pause between two attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Delivers job notifications to the callback URLs clients registered, with
+/// a client of its own, so a slow endpoint cannot hold up crawls.
+#[derive(Clone)]
+pub struct Callbacks {
+    client: reqwest::Client,
+    retries: u32,
+    /// Deliveries in progress, retries and the pauses between them included.
+    permits: Arc<Semaphore>,
+}
+
+impl Callbacks {
+    pub fn new(
+        timeout: Duration,
+        retries: u32,
+        max_concurrency: usize,
+        accept_invalid_certs: bool,
+    ) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            // A redirect would resend the results somewhere the client did not name.
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .context("Failed to initialize callback HTTP client")?;
+        Ok(Callbacks {
+            client,
+            retries,
+            permits: Arc::new(Semaphore::new(max_concurrency)),
+        })
+    }
+
+    pub fn validate(url: &str) -> Result<(), String> {
+        let parsed =
+            Url::parse(url).map_err(|e| format!("invalid callback_url '{}': {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("callback_url must be http or https: {}", url));
+        }
+        Ok(())
+    }
+
+    /// POSTs `body` as JSON to `url` once a delivery slot is free. Timeouts,
+    /// connection errors, `408`, `429` and `5xx` answers are retried up to
+    /// `retries` times, pausing 1s, 2s, 4s and so on, at most 30s, in between.
+    /// Other answers besides `2xx` fail right away.
+    pub async fn deliver(&self, url: &str, body: &impl Serialize) -> Result<()> {
+        let _permit = self.permits.acquire().await?;
+        let body = serde_json::to_vec(body)?;
+        let mut backoff = INITIAL_BACKOFF;
+        let mut retries = 0;
+        loop {
+            let error = match self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) if !retryable(response.status()) => {
+                    return Err(anyhow!("callback answered {}", response.status()));
+                }
+                Ok(response) => anyhow!("callback answered {}", response.status()),
+                Err(e) => anyhow::Error::new(e).context("callback request failed"),
+            };
+            if retries == self.retries {
+                return Err(error);
+            }
+            retries += 1;
+            warn!(
+                "Callback to {} failed, retrying in {}s: {:#}",
+                url,
+                backoff.as_secs(),
+                error
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode as Answer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves `answers` in turn, the last one from then on, and counts the requests.
+    async fn endpoint(answers: Vec<Answer>) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::post(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let answer = answers[n.min(answers.len() - 1)];
+                async move { answer }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, requests)
+    }
+
+    fn callbacks(retries: u32) -> Callbacks {
+        Callbacks::new(Duration::from_secs(5), retries, 1, false).unwrap()
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (url, requests) = endpoint(vec![Answer::SERVICE_UNAVAILABLE, Answer::OK]).await;
+        callbacks(3).deliver(&url, &"done").await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_retries() {
+        let (url, requests) = endpoint(vec![Answer::TOO_MANY_REQUESTS]).await;
+        assert!(callbacks(1).deliver(&url, &"done").await.is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, requests) = endpoint(vec![Answer::NOT_FOUND, Answer::OK]).await;
+        let error = callbacks(3).deliver(&url, &"done").await.unwrap_err();
+        assert_eq!(error.to_string(), "callback answered 404 Not Found");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn validates_urls() {
+        assert!(Callbacks::validate("https://example.com/hook").is_ok());
+        assert!(Callbacks::validate("ftp://example.com/hook").is_err());
+        assert!(Callbacks::validate("/hook").is_err());
+    }
+}
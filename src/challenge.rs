@@ -0,0 +1,133 @@
+use reqwest::header::HeaderMap;
+use scraper::{Html, Selector};
+use spider::features::chrome_common::WebAutomation;
+
+/// Titles of bot challenge interstitials, lowercased. Matched as prefixes,
+/// since some are followed by the site's name.
+const TITLES: &[&str] = &[
+    "just a moment...",
+    "attention required! | cloudflare",
+    "checking your browser",
+    "ddos-guard",
+];
+
+/// Elements of Cloudflare challenge pages, old and current.
+const SELECTORS: &[&str] = &[
+    "#challenge-form",
+    "#challenge-running",
+    "#challenge-stage",
+    "#cf-challenge-running",
+    "#cf-please-wait",
+    ".cf-browser-verification",
+];
+
+/// Global the Cloudflare challenge platform is configured with. Ordinary pages
+/// behind Cloudflare load `/cdn-cgi/challenge-platform/` scripts as well, so
+/// the script path alone says nothing.
+const SCRIPT_MARKER: &str = "_cf_chl_opt";
+
+/// How often [`wait`] checks whether the challenge cleared.
+const POLL_MS: u64 = 250;
+
+/// Whether the page is a bot challenge interstitial rather than its content:
+/// one of the known titles, challenge elements or the challenge script.
+pub fn detected(document: &Html) -> bool {
+    let title = Selector::parse("title").expect("valid title selector");
+    let titled = document.select(&title).next().is_some_and(|title| {
+        let title = title.text().collect::<String>().trim().to_lowercase();
+        TITLES.iter().any(|prefix| title.starts_with(prefix))
+    });
+    let elements = Selector::parse(&SELECTORS.join(",")).expect("valid challenge selectors");
+    let scripts = Selector::parse("script").expect("valid script selector");
+    titled
+        || document.select(&elements).next().is_some()
+        || document.select(&scripts).any(|script| {
+            // `text()` leaves out the contents of `<script>` elements.
+            script
+                .children()
+                .filter_map(|node| node.value().as_text())
+                .any(|text| text.contains(SCRIPT_MARKER))
+        })
+}
+
+/// Whether Cloudflare answered with a challenge instead of the origin's
+/// response: marked `cf-mitigated: challenge`, or a 403 or 503 with `cf-ray`.
+pub fn response(status: Option<u16>, headers: Option<&HeaderMap>) -> bool {
+    let Some(headers) = headers else {
+        return false;
+    };
+    let mitigated = headers
+        .get("cf-mitigated")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("challenge"));
+    mitigated || (matches!(status, Some(403 | 503)) && headers.contains_key("cf-ray"))
+}
+
+/// Automation steps that give a challenge up to `timeout_ms` to clear before
+/// the page is captured. Pages without one pass right away. A challenge that
+/// clears usually reloads the page, which ends the check early, so the steps
+/// then wait for the reloaded page's body.
+pub fn wait(timeout_ms: u64) -> Vec<WebAutomation> {
+    let titles = serde_json::Value::from(TITLES.to_vec());
+    let selectors = serde_json::Value::from(SELECTORS.join(","));
+    vec![
+        WebAutomation::Evaluate(format!(
+            "(() => new Promise(resolve => {{
+                const titles = {titles};
+                const challenged = () =>
+                    titles.some(t => document.title.trim().toLowerCase().startsWith(t))
+                    || document.querySelector({selectors}) !== null
+                    || typeof window.{SCRIPT_MARKER} !== 'undefined';
+                const deadline = Date.now() + {timeout_ms};
+                const check = () => !challenged() || Date.now() > deadline
+                    ? resolve()
+                    : setTimeout(check, {POLL_MS});
+                check();
+            }}))()"
+        )),
+        WebAutomation::WaitForDom {
+            selector: Some("body".to_string()),
+            timeout: 5000,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_challenge_script() {
+        let page = Html::parse_document(
+            "<html><head><title>example.com</title></head><body>\
+             <script>window._cf_chl_opt = {cType: 'managed'};</script></body></html>",
+        );
+        assert!(detected(&page));
+    }
+
+    #[test]
+    fn detects_challenge_title() {
+        let page = Html::parse_document("<title>Just a moment...</title>");
+        assert!(detected(&page));
+    }
+
+    #[test]
+    fn ignores_ordinary_pages() {
+        let page = Html::parse_document(
+            "<title>News</title><p>_cf_chl_opt</p>\
+             <script src=\"/cdn-cgi/challenge-platform/scripts/jsd/main.js\"></script>",
+        );
+        assert!(!detected(&page));
+    }
+
+    #[test]
+    fn detects_challenge_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert("cf-ray", "8a1b2c3d4e5f-AMS".parse().unwrap());
+        assert!(response(Some(403), Some(&headers)));
+        assert!(!response(Some(200), Some(&headers)));
+        headers.insert("cf-mitigated", "challenge".parse().unwrap());
+        assert!(response(Some(200), Some(&headers)));
+        assert!(!response(Some(403), None));
+    }
+}
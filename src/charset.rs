@@ -0,0 +1,149 @@
+use encoding_rs::Encoding;
+
+/// Bytes searched for a `<meta>` charset, like the HTML prescan.
+const PRESCAN_BYTES: usize = 1024;
+
+/// Character encoding a page declares: the `charset` of its `Content-Type`,
+/// or else of a `<meta charset>` or `<meta http-equiv="Content-Type">` tag.
+pub fn declared(content_type: Option<&str>, html: &[u8]) -> Option<&'static Encoding> {
+    content_type
+        .and_then(charset_param)
+        .or_else(|| meta_charset(html))
+        .and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+}
+
+/// Decodes raw response bytes in `encoding`. A byte order mark wins over the
+/// declaration; undeclared pages are read as UTF-8 if valid, else guessed.
+pub fn decode(html: &[u8], encoding: Option<&'static Encoding>) -> String {
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => match std::str::from_utf8(html) {
+            Ok(html) => return html.to_string(),
+            Err(_) => {
+                let mut detector = chardetng::EncodingDetector::new();
+                detector.feed(html, true);
+                detector.guess(None, true)
+            }
+        },
+    };
+    encoding.decode(html).0.into_owned()
+}
+
+/// Like [`decode`], for pages crawled by spider: Chrome hands out the DOM as
+/// UTF-8 whatever the page declared, so valid UTF-8 is taken as is. Pages
+/// spider fetched itself keep their original bytes.
+pub fn decode_crawled(html: &[u8], encoding: Option<&'static Encoding>) -> String {
+    match std::str::from_utf8(html) {
+        Ok(html) => html.to_string(),
+        Err(_) => decode(html, encoding),
+    }
+}
+
+/// The `charset` parameter of a `Content-Type`-like value.
+fn charset_param(value: &str) -> Option<&str> {
+    value.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+    })
+}
+
+fn meta_charset(html: &[u8]) -> Option<&str> {
+    let head = &html[..html.len().min(PRESCAN_BYTES)];
+    let lower = head.to_ascii_lowercase();
+    let mut rest = lower.as_slice();
+    while let Some(start) = find(rest, b"<meta") {
+        let tag = &rest[start..];
+        let tag = &tag[..find(tag, b">").unwrap_or(tag.len())];
+        if let Some(charset) = find(tag, b"charset=") {
+            let offset = lower.len() - rest.len() + start + charset + b"charset=".len();
+            let value = &head[offset..];
+            let value = value
+                .strip_prefix(b"\"")
+                .or_else(|| value.strip_prefix(b"'"))
+                .unwrap_or(value);
+            let end = value
+                .iter()
+                .position(|b| {
+                    matches!(b, b'"' | b'\'' | b';' | b'>' | b'/') || b.is_ascii_whitespace()
+                })
+                .unwrap_or(value.len());
+            return std::str::from_utf8(&value[..end]).ok();
+        }
+        rest = &rest[start + tag.len()..];
+    }
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `日本語` in Shift_JIS.
+    const SHIFT_JIS_TEXT: &[u8] = b"\x93\xfa\x96\x7b\x8c\xea";
+    /// `Привет мир` in Windows-1251.
+    const WINDOWS_1251_TEXT: &[u8] = b"\xcf\xf0\xe8\xe2\xe5\xf2 \xec\xe8\xf0";
+
+    fn page(head: &str, text: &[u8]) -> Vec<u8> {
+        [
+            format!("<html><head>{}</head><body><p>", head).as_bytes(),
+            text,
+            b"</p></body></html>",
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn decodes_meta_charset() {
+        let html = page(r#"<meta charset="Shift_JIS">"#, SHIFT_JIS_TEXT);
+        let encoding = declared(None, &html);
+        assert_eq!(encoding, Some(encoding_rs::SHIFT_JIS));
+        assert!(decode(&html, encoding).contains("<p>日本語</p>"));
+    }
+
+    #[test]
+    fn decodes_http_equiv_charset() {
+        let html = page(
+            r#"<meta http-equiv="Content-Type" content="text/html; charset=windows-1251">"#,
+            WINDOWS_1251_TEXT,
+        );
+        let encoding = declared(None, &html);
+        assert_eq!(encoding, Some(encoding_rs::WINDOWS_1251));
+        assert!(decode(&html, encoding).contains("<p>Привет мир</p>"));
+    }
+
+    #[test]
+    fn content_type_wins_over_meta() {
+        let html = page(r#"<meta charset="utf-8">"#, WINDOWS_1251_TEXT);
+        let encoding = declared(Some("text/html; charset=\"windows-1251\""), &html);
+        assert_eq!(encoding, Some(encoding_rs::WINDOWS_1251));
+    }
+
+    #[test]
+    fn guesses_undeclared_pages() {
+        let html = page(
+            "",
+            b"\xcf\xf0\xe8\xe2\xe5\xf2 \xec\xe8\xf0, \xfd\xf2\xee \xf2\xe5\xf1\xf2",
+        );
+        assert_eq!(declared(None, &html), None);
+        assert!(decode(&html, None).contains("Привет мир, это тест"));
+        assert_eq!(decode(b"<p>caf\xc3\xa9</p>", None), "<p>café</p>");
+    }
+
+    #[test]
+    fn crawled_utf8_is_kept() {
+        // Chrome serializes the DOM as UTF-8 even for Shift_JIS pages.
+        let html = r#"<meta charset="Shift_JIS"><p>日本語</p>"#;
+        let encoding = declared(None, html.as_bytes());
+        assert_eq!(decode_crawled(html.as_bytes(), encoding), html);
+        let raw = page(r#"<meta charset="Shift_JIS">"#, SHIFT_JIS_TEXT);
+        assert!(decode_crawled(&raw, declared(None, &raw)).contains("日本語"));
+    }
+}
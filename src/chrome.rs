@@ -0,0 +1,134 @@
+use log::{error, info, warn};
+use reqwest::header::HeaderMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Tracks whether the remote Chrome instance is reachable and waits for it to
+/// come back, with capped exponential backoff, when a crawl loses it.
+pub struct Chrome {
+    url: Option<String>,
+    http_client: reqwest::Client,
+    /// Sent with every probe, e.g. for a gateway in front of Chrome.
+    probe_headers: HeaderMap,
+    max_attempts: u32,
+    max_backoff: Duration,
+    reconnecting: AtomicBool,
+    attempt: AtomicU32,
+    /// Held while reconnecting so concurrent crawls wait for a single loop.
+    reconnect_lock: Mutex<()>,
+}
+
+/// Connection state reported by `/health`.
+pub enum ChromeStatus {
+    Connected,
+    Reconnecting { attempt: u32 },
+}
+
+impl Chrome {
+    pub fn new(
+        url: Option<String>,
+        http_client: reqwest::Client,
+        probe_headers: HeaderMap,
+        max_attempts: u32,
+        max_backoff: Duration,
+    ) -> Self {
+        Chrome {
+            url,
+            http_client,
+            probe_headers,
+            max_attempts,
+            max_backoff,
+            reconnecting: AtomicBool::new(false),
+            attempt: AtomicU32::new(0),
+            reconnect_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn status(&self) -> ChromeStatus {
+        if self.reconnecting.load(Ordering::Relaxed) {
+            ChromeStatus::Reconnecting {
+                attempt: self.attempt.load(Ordering::Relaxed),
+            }
+        } else {
+            ChromeStatus::Connected
+        }
+    }
+
+    /// Checks that the Chrome endpoint answers with a success status.
+    pub async fn probe(&self) -> Result<(), String> {
+        let Some(url) = &self.url else {
+            return Err("Chromium connection URL not configured".to_string());
+        };
+        let request = self
+            .http_client
+            .get(url)
+            .headers(self.probe_headers.clone());
+        match request.send().await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!(
+                "received non-success status code {}",
+                resp.status()
+            )),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Called before a batch so its URLs are not all crawled against a lost
+    /// Chrome: waits for a reconnect when the probe fails.
+    pub async fn wait_connected(&self) -> Result<(), String> {
+        let Err(e) = self.probe().await else {
+            return Ok(());
+        };
+        // `recover` reports `false` if Chrome came back before it probed.
+        if self.recover().await || self.probe().await.is_ok() {
+            Ok(())
+        } else {
+            Err(e)
+        }
+    }
+
+    /// Called after a crawl came back empty. Returns `true` if Chrome was lost
+    /// and is reachable again, i.e. the crawl is worth retrying.
+    pub async fn recover(&self) -> bool {
+        if self.url.is_none() || self.probe().await.is_ok() {
+            return false;
+        }
+
+        let _guard = self.reconnect_lock.lock().await;
+        // Another crawl may have finished reconnecting while we waited.
+        if self.probe().await.is_ok() {
+            return true;
+        }
+
+        self.reconnecting.store(true, Ordering::Relaxed);
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=self.max_attempts {
+            self.attempt.store(attempt, Ordering::Relaxed);
+            warn!(
+                "Chromium connection lost, reconnect attempt {}/{} in {:?}",
+                attempt, self.max_attempts, backoff
+            );
+            tokio::time::sleep(backoff).await;
+            if self.probe().await.is_ok() {
+                info!("Reconnected to Chromium after {} attempts", attempt);
+                self.reconnecting.store(false, Ordering::Relaxed);
+                return true;
+            }
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+
+        error!(
+            "Giving up reconnecting to Chromium after {} attempts",
+            self.max_attempts
+        );
+        self.reconnecting.store(false, Ordering::Relaxed);
+        false
+    }
+}
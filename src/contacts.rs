@@ -0,0 +1,101 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use utoipa::ToSchema;
+
+/// `local@domain.tld`, with the characters commonly seen in addresses rather
+/// than everything RFC 5322 allows.
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,24}")
+        .expect("valid email regex")
+});
+
+/// Digit groups separated by spaces, dots or dashes, optionally led by a
+/// `+` country code and with one parenthesized group, e.g. `+1 (555) 010-9999`.
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,5}\)[ .-]?)?\d{1,5}(?:[ .-]\d{1,5}){0,5}")
+        .expect("valid phone regex")
+});
+
+/// Dates that look like digit groups, e.g. `2024-01-31` or `31.01.2024`.
+static DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\d{4}[.-]\d{1,2}[.-]\d{1,2}|\d{1,2}[.-]\d{1,2}[.-]\d{2,4})$")
+        .expect("valid date regex")
+});
+
+/// File extensions that end image names such as `logo@2x.png`, not domains.
+const FILE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "ico", "css", "js",
+];
+
+/// Email addresses and phone numbers returned by `extract_contacts`.
+#[derive(Clone, Default, Serialize, ToSchema)]
+pub struct Contacts {
+    #[schema(example = json!(["sales@example.com"]))]
+    pub emails: Vec<String>,
+    #[schema(example = json!(["+1 (555) 010-9999"]))]
+    pub phones: Vec<String>,
+}
+
+/// Scans `text` for email addresses and phone numbers, in order of appearance.
+/// Emails are deduplicated ignoring case, phone numbers by their digits.
+pub fn extract(text: &str) -> Contacts {
+    let mut seen = HashSet::new();
+    let emails = EMAIL
+        .find_iter(text)
+        .map(|m| m.as_str().trim_matches('.'))
+        .filter(|email| is_email(email))
+        .filter(|email| seen.insert(email.to_ascii_lowercase()))
+        .map(str::to_string)
+        .collect();
+
+    let mut seen = HashSet::new();
+    let phones = PHONE
+        .find_iter(text)
+        .filter(|m| standalone(text, m.start(), m.end()))
+        .map(|m| m.as_str())
+        .filter(|phone| is_phone(phone))
+        .filter(|phone| {
+            let digits: String = phone
+                .chars()
+                .filter(|c| c.is_ascii_digit() || *c == '+')
+                .collect();
+            seen.insert(digits)
+        })
+        .map(str::to_string)
+        .collect();
+
+    Contacts { emails, phones }
+}
+
+fn is_email(email: &str) -> bool {
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+    let tld = domain.rsplit('.').next().unwrap_or_default();
+    !local.is_empty()
+        && !local.starts_with('.')
+        && !local.contains("..")
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+        && !FILE_EXTENSIONS.contains(&tld.to_ascii_lowercase().as_str())
+}
+
+/// Between 7 and 15 digits, the E.164 maximum, and written like a phone
+/// number: with a country code, an area code in parentheses or separators.
+/// Plain digit runs are more often IDs, prices or years.
+fn is_phone(phone: &str) -> bool {
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+    let formatted = phone.starts_with('+') || phone.contains('(') || phone.contains([' ', '-']);
+    (7..=15).contains(&digits) && formatted && !DATE.is_match(phone)
+}
+
+/// Whether the match is not part of a longer token, e.g. a URL path or version.
+fn standalone(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    let joins = |c: char| c.is_alphanumeric() || matches!(c, '/' | '_' | '=' | '&' | '#');
+    !before.is_some_and(joins) && !after.is_some_and(joins)
+}
@@ -0,0 +1,215 @@
+use jiff::fmt::rfc2822::DateTimeParser;
+use jiff::fmt::strtime;
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, civil};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// JSON-LD nested deeper than this is not searched.
+const MAX_DEPTH: usize = 32;
+
+/// `property` of the Open Graph tags, published then modified.
+const OPEN_GRAPH: [&[&str]; 2] = [
+    &["article:published_time", "og:published_time"],
+    &["article:modified_time", "og:updated_time"],
+];
+
+/// `name` of other meta tags, lowercased, published then modified.
+const META_NAMES: [&[&str]; 2] = [
+    &[
+        "date",
+        "pubdate",
+        "publishdate",
+        "publish_date",
+        "publication_date",
+        "dc.date",
+        "dc.date.issued",
+        "dcterms.issued",
+        "dcterms.created",
+        "citation_publication_date",
+        "parsely-pub-date",
+        "sailthru.date",
+    ],
+    &["last-modified", "dc.date.modified", "dcterms.modified"],
+];
+
+/// Microdata properties and JSON-LD keys, published then modified.
+const PROPERTIES: [&str; 2] = ["datePublished", "dateModified"];
+
+/// Written out dates in English, e.g. `January 31, 2024` or `31 Jan 2024`.
+const WRITTEN_FORMATS: &[&str] = &["%B %d, %Y", "%b %d, %Y", "%d %B %Y", "%d %b %Y"];
+
+/// Where a date of [`PageDates`] was found.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DateSource {
+    /// `datePublished`/`dateModified` of a JSON-LD item.
+    Jsonld,
+    /// `article:published_time`, `article:modified_time` and the like.
+    Opengraph,
+    /// Other meta tags, e.g. `<meta name="date">` or microdata `itemprop`s.
+    Meta,
+    /// `<time datetime>` elements.
+    Time,
+}
+
+/// Publish and modified dates of a page, as RFC 3339 timestamps in UTC.
+#[derive(Clone, Debug, Default)]
+pub struct PageDates {
+    pub published: Option<(String, DateSource)>,
+    pub modified: Option<(String, DateSource)>,
+}
+
+/// The page's publish and modified dates from JSON-LD, then Open Graph, then
+/// other meta tags, then `<time>` elements. Each date is taken from the first
+/// source with a value that parses; values that do not are skipped.
+pub fn extract(document: &Html) -> PageDates {
+    let json_ld = crate::extract::json_ld(document);
+    let [published, modified] = [0, 1].map(|kind| {
+        json_ld_date(&json_ld, PROPERTIES[kind])
+            .map(|date| (date, DateSource::Jsonld))
+            .or_else(|| {
+                meta_date(document, "property", OPEN_GRAPH[kind])
+                    .map(|date| (date, DateSource::Opengraph))
+            })
+            .or_else(|| {
+                meta_date(document, "name", META_NAMES[kind])
+                    .or_else(|| meta_date(document, "itemprop", &[PROPERTIES[kind]]))
+                    .map(|date| (date, DateSource::Meta))
+            })
+            .or_else(|| time_date(document, kind == 0).map(|date| (date, DateSource::Time)))
+    });
+    PageDates {
+        published,
+        modified,
+    }
+}
+
+/// Normalizes `value` to an RFC 3339 timestamp in UTC. Dates and times
+/// without an offset are taken as UTC. Formats whose reading depends on the
+/// locale, such as `01/02/2024`, give `None` rather than a guess.
+fn normalize(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let utc = |datetime: civil::DateTime| {
+        datetime
+            .to_zoned(TimeZone::UTC)
+            .ok()
+            .map(|zoned| zoned.timestamp())
+    };
+    let timestamp = value
+        .parse::<Timestamp>()
+        .ok()
+        .or_else(|| value.parse::<civil::DateTime>().ok().and_then(utc))
+        .or_else(|| DateTimeParser::new().parse_timestamp(value).ok())
+        .or_else(|| {
+            // Year first is unambiguous, unlike day or month first.
+            let year_first = value.replace(['/', '.'], "-");
+            (value.len() >= 8 && value.bytes().take(4).all(|b| b.is_ascii_digit()))
+                .then(|| strtime::parse("%Y-%m-%d", &year_first).ok()?.to_date().ok())
+                .flatten()
+                .or_else(|| {
+                    WRITTEN_FORMATS
+                        .iter()
+                        .find_map(|format| strtime::parse(format, value).ok()?.to_date().ok())
+                })
+                .and_then(|date| utc(date.to_datetime(civil::Time::midnight())))
+        })?;
+    Some(timestamp.to_string())
+}
+
+/// The first value of `key` that parses, searching the items depth first in
+/// document order, e.g. through `@graph`.
+fn json_ld_date(items: &[Value], key: &str) -> Option<String> {
+    let mut stack: Vec<_> = items.iter().rev().map(|item| (item, 0)).collect();
+    while let Some((value, depth)) = stack.pop() {
+        if depth > MAX_DEPTH {
+            continue;
+        }
+        match value {
+            Value::Object(object) => {
+                if let Some(date) = object.get(key).and_then(Value::as_str).and_then(normalize) {
+                    return Some(date);
+                }
+                stack.extend(object.values().rev().map(|v| (v, depth + 1)));
+            }
+            Value::Array(values) => stack.extend(values.iter().rev().map(|v| (v, depth + 1))),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The first `<meta>` whose `attr` is one of `names` and whose content parses.
+fn meta_date(document: &Html, attr: &str, names: &[&str]) -> Option<String> {
+    let selector =
+        Selector::parse(&format!("meta[{}][content]", attr)).expect("valid meta selector");
+    document
+        .select(&selector)
+        .filter(|meta| {
+            let name = meta.value().attr(attr).unwrap_or_default().trim();
+            names.iter().any(|n| n.eq_ignore_ascii_case(name))
+        })
+        .find_map(|meta| normalize(meta.value().attr("content")?))
+}
+
+/// A `<time>` marked as the publish or modified date. Without a marked one,
+/// the first `<time datetime>` counts as the publish date, within
+/// `<article>` if the page has one.
+fn time_date(document: &Html, published: bool) -> Option<String> {
+    let marked = if published {
+        "time[datetime][itemprop~=datePublished], time[datetime][pubdate]"
+    } else {
+        "time[datetime][itemprop~=dateModified]"
+    };
+    let mut selectors = vec![marked];
+    if published {
+        selectors.extend(["article time[datetime]", "time[datetime]"]);
+    }
+    selectors.into_iter().find_map(|selector| {
+        let selector = Selector::parse(selector).expect("valid time selector");
+        document
+            .select(&selector)
+            .find_map(|time| normalize(time.value().attr("datetime")?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_json_ld() {
+        let page = Html::parse_document(
+            r#"<head>
+            <meta property="article:published_time" content="2023-01-01T00:00:00Z">
+            <script type="application/ld+json">
+            {"@graph": [{"@type": "NewsArticle", "datePublished": "2024-03-05T10:00:00+01:00"}]}
+            </script>
+            <meta name="last-modified" content="March 6, 2024">
+            </head>"#,
+        );
+        let dates = extract(&page);
+        assert_eq!(
+            dates.published,
+            Some(("2024-03-05T09:00:00Z".to_string(), DateSource::Jsonld))
+        );
+        assert_eq!(
+            dates.modified,
+            Some(("2024-03-06T00:00:00Z".to_string(), DateSource::Meta))
+        );
+    }
+
+    #[test]
+    fn skips_ambiguous_dates() {
+        assert_eq!(normalize("01/02/2024"), None);
+        assert_eq!(
+            normalize("2024/02/01"),
+            Some("2024-02-01T00:00:00Z".to_string())
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt::Write;
+
+/// Lines of context around each hunk, as in `diff -u`.
+const CONTEXT: usize = 3;
+/// Largest LCS table computed; bigger changes are reported as a full rewrite.
+const MAX_CELLS: usize = 16 * 1024 * 1024;
+
+pub struct LineDiff {
+    /// `2 * common lines / total lines`, 1.0 for identical content.
+    pub similarity: f64,
+    /// Unified diff of the two versions, empty if they are equal.
+    pub unified: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One step of the edit script, with the positions in both versions before it.
+#[derive(Clone, Copy)]
+struct Edit {
+    op: Op,
+    old: usize,
+    new: usize,
+}
+
+/// Line diff of `old` and `new`. Lines are compared with whitespace collapsed,
+/// so changes in indentation or spacing alone are not reported.
+pub fn diff(old: &str, new: &str) -> LineDiff {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let old_keys: Vec<String> = old_lines.iter().map(|l| normalize(l)).collect();
+    let new_keys: Vec<String> = new_lines.iter().map(|l| normalize(l)).collect();
+
+    let edits = edit_script(&old_keys, &new_keys);
+    let common = edits.iter().filter(|e| e.op == Op::Equal).count();
+    let total = old_lines.len() + new_lines.len();
+    let similarity = if total == 0 {
+        1.0
+    } else {
+        (2 * common) as f64 / total as f64
+    };
+
+    LineDiff {
+        similarity,
+        unified: unified(&edits, &old_lines, &new_lines),
+    }
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn edit_script(old: &[String], new: &[String]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut edits = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    let mut push = |op, i: usize, j: usize| edits.push(Edit { op, old: i, new: j });
+
+    for _ in 0..prefix {
+        push(Op::Equal, i, j);
+        i += 1;
+        j += 1;
+    }
+
+    let width = b.len() + 1;
+    if a.len().saturating_mul(b.len()) <= MAX_CELLS {
+        // lcs[x * width + y]: length of the LCS of a[x..] and b[y..].
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for x in (0..a.len()).rev() {
+            for y in (0..b.len()).rev() {
+                lcs[x * width + y] = if a[x] == b[y] {
+                    lcs[(x + 1) * width + y + 1] + 1
+                } else {
+                    lcs[(x + 1) * width + y].max(lcs[x * width + y + 1])
+                };
+            }
+        }
+        let (mut x, mut y) = (0, 0);
+        while x < a.len() || y < b.len() {
+            if x < a.len() && y < b.len() && a[x] == b[y] {
+                push(Op::Equal, i, j);
+                x += 1;
+                y += 1;
+                i += 1;
+                j += 1;
+            } else if y == b.len()
+                || (x < a.len() && lcs[(x + 1) * width + y] >= lcs[x * width + y + 1])
+            {
+                push(Op::Delete, i, j);
+                x += 1;
+                i += 1;
+            } else {
+                push(Op::Insert, i, j);
+                y += 1;
+                j += 1;
+            }
+        }
+    } else {
+        for _ in 0..a.len() {
+            push(Op::Delete, i, j);
+            i += 1;
+        }
+        for _ in 0..b.len() {
+            push(Op::Insert, i, j);
+            j += 1;
+        }
+    }
+
+    for _ in 0..suffix {
+        push(Op::Equal, i, j);
+        i += 1;
+        j += 1;
+    }
+    edits
+}
+
+fn unified(edits: &[Edit], old: &[&str], new: &[&str]) -> String {
+    let changes: Vec<usize> = (0..edits.len())
+        .filter(|&k| edits[k].op != Op::Equal)
+        .collect();
+    let Some(&first) = changes.first() else {
+        return String::new();
+    };
+
+    // Group changes whose context would overlap into one hunk.
+    let mut hunks = vec![(first, first)];
+    for &k in &changes[1..] {
+        let last = hunks.last_mut().expect("at least one hunk");
+        if k - last.1 <= 2 * CONTEXT + 1 {
+            last.1 = k;
+        } else {
+            hunks.push((k, k));
+        }
+    }
+
+    let mut out = String::from("--- cached\n+++ fresh\n");
+    for (start, end) in hunks {
+        let start = start.saturating_sub(CONTEXT);
+        let end = (end + CONTEXT + 1).min(edits.len());
+        let hunk = &edits[start..end];
+        let old_count = hunk.iter().filter(|e| e.op != Op::Insert).count();
+        let new_count = hunk.iter().filter(|e| e.op != Op::Delete).count();
+        let position = |start: usize, count: usize| if count == 0 { start } else { start + 1 };
+        let _ = writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            position(hunk[0].old, old_count),
+            old_count,
+            position(hunk[0].new, new_count),
+            new_count
+        );
+        for edit in hunk {
+            let _ = match edit.op {
+                Op::Equal => writeln!(out, " {}", old[edit.old]),
+                Op::Delete => writeln!(out, "-{}", old[edit.old]),
+                Op::Insert => writeln!(out, "+{}", new[edit.new]),
+            };
+        }
+    }
+    out
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result, bail};
+use log::{error, info, warn};
+use serde::Serialize;
+use spider::url::Url;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Outcome of a crawl as published to the event broker.
+#[derive(Serialize)]
+pub struct CrawlEvent {
+    pub url: String,
+    pub crawled_at: String,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    /// Hex encoded hash of the converted content, see `APP_CONTENT_HASH_ALGORITHM`.
+    pub content_hash: String,
+}
+
+/// Handle to the background task publishing crawl events to Redis pub/sub.
+#[derive(Clone)]
+pub struct Events {
+    tx: mpsc::Sender<CrawlEvent>,
+}
+
+impl Events {
+    /// Parses the `redis://[:password@]host[:port]` broker URL and spawns the
+    /// publisher. The connection is opened lazily and re-opened after errors.
+    pub fn spawn(broker_url: &str, channel: String) -> Result<Self> {
+        let url = Url::parse(broker_url).context("Invalid event broker URL")?;
+        if url.scheme() != "redis" {
+            bail!("Unsupported event broker scheme '{}'", url.scheme());
+        }
+        let host = url.host_str().context("Event broker URL has no host")?;
+        let publisher = Publisher {
+            addr: format!("{}:{}", host, url.port().unwrap_or(6379)),
+            password: url.password().map(str::to_string),
+            channel,
+            conn: None,
+        };
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(publisher.run(rx));
+        Ok(Events { tx })
+    }
+
+    /// Queues an event without waiting; events are dropped when the broker
+    /// cannot keep up or is unreachable.
+    pub fn publish(&self, event: CrawlEvent) {
+        if let Err(e) = self.tx.try_send(event) {
+            warn!("Dropping crawl event: {}", e);
+        }
+    }
+}
+
+struct Publisher {
+    addr: String,
+    password: Option<String>,
+    channel: String,
+    conn: Option<BufReader<TcpStream>>,
+}
+
+impl Publisher {
+    async fn run(mut self, mut rx: mpsc::Receiver<CrawlEvent>) {
+        while let Some(event) = rx.recv().await {
+            if let Err(e) = self.publish(&event).await {
+                error!("Failed to publish crawl event for {}: {:#}", event.url, e);
+                self.conn = None;
+            }
+        }
+    }
+
+    async fn publish(&mut self, event: &CrawlEvent) -> Result<()> {
+        let payload = serde_json::to_vec(event)?;
+        let conn = match &mut self.conn {
+            Some(conn) => conn,
+            None => {
+                let mut conn = BufReader::new(
+                    TcpStream::connect(&self.addr)
+                        .await
+                        .with_context(|| format!("Failed to connect to {}", self.addr))?,
+                );
+                if let Some(password) = &self.password {
+                    command(&mut conn, &[b"AUTH", password.as_bytes()]).await?;
+                }
+                info!("Connected to event broker at {}", self.addr);
+                self.conn.insert(conn)
+            }
+        };
+        command(conn, &[b"PUBLISH", self.channel.as_bytes(), &payload]).await
+    }
+}
+
+/// Sends a RESP command and fails on an error reply.
+async fn command(conn: &mut BufReader<TcpStream>, args: &[&[u8]]) -> Result<()> {
+    let mut buf = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        buf.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        buf.extend_from_slice(arg);
+        buf.extend_from_slice(b"\r\n");
+    }
+    conn.get_mut().write_all(&buf).await?;
+
+    let mut reply = String::new();
+    if conn.read_line(&mut reply).await? == 0 {
+        bail!("connection closed by broker");
+    }
+    if let Some(message) = reply.strip_prefix('-') {
+        bail!("broker replied: {}", message.trim_end());
+    }
+    Ok(())
+}
@@ -0,0 +1,364 @@
+use log::warn;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use serde_json::Value;
+use spider::url::Url;
+use utoipa::ToSchema;
+
+/// Entry of the page outline returned by `include_outline`.
+#[derive(Clone, Serialize, ToSchema)]
+pub struct Heading {
+    /// 1 for `<h1>` through 6 for `<h6>`.
+    pub level: u8,
+    pub text: String,
+    /// Anchor to deep-link the heading: its own `id`, else that of an `<a>` inside it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+/// Parses every `<script type="application/ld+json">` block of the document.
+///
+/// Blocks that are not valid JSON are skipped.
+pub fn json_ld(document: &Html) -> Vec<Value> {
+    let selector =
+        Selector::parse(r#"script[type="application/ld+json"]"#).expect("valid JSON-LD selector");
+
+    document
+        .select(&selector)
+        .filter_map(|script| {
+            // `text()` leaves out the contents of `<script>` elements.
+            let raw: String = script
+                .children()
+                .filter_map(|node| node.value().as_text().map(|text| &**text))
+                .collect();
+            match serde_json::from_str(raw.trim()) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    warn!("Skipping invalid JSON-LD block: {}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Outer HTML of every element matching `selector`, or `None` if nothing matches.
+pub fn subtree(document: &Html, selector: &Selector) -> Option<String> {
+    let html: String = document.select(selector).map(|e| e.html()).collect();
+    (!html.is_empty()).then_some(html)
+}
+
+/// Video or audio declared by Open Graph tags, returned by `include_media`.
+#[derive(Clone, Serialize, ToSchema)]
+pub struct MediaRef {
+    /// `video` or `audio`.
+    pub kind: &'static str,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Seconds, from `og:video:duration` or `video:duration`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+}
+
+/// The page's `og:video` and `og:audio` references with their structured
+/// properties. Each `og:video`/`og:video:url` tag starts a new entry that the
+/// following `og:video:*` tags describe, as the Open Graph protocol specifies.
+pub fn media(document: &Html, base: &Url) -> Vec<MediaRef> {
+    let selector = Selector::parse("meta[property][content]").expect("valid meta selector");
+    let resolve = |value: &str| base.join(value.trim()).ok().map(String::from);
+    let mut items: Vec<MediaRef> = Vec::new();
+
+    for meta in document.select(&selector) {
+        let property = meta
+            .value()
+            .attr("property")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let content = meta.value().attr("content").unwrap_or_default();
+        let property = property.strip_prefix("og:").unwrap_or(&property);
+        let Some((kind, field)) = ["video", "audio"].into_iter().find_map(|kind| {
+            let rest = property.strip_prefix(kind)?;
+            let field = match rest {
+                "" => "url",
+                _ => rest.strip_prefix(':')?,
+            };
+            Some((kind, field))
+        }) else {
+            continue;
+        };
+
+        if field == "url" {
+            if let Some(url) = resolve(content) {
+                items.push(MediaRef {
+                    kind,
+                    url,
+                    secure_url: None,
+                    mime_type: None,
+                    width: None,
+                    height: None,
+                    duration: None,
+                });
+            }
+            continue;
+        }
+        let Some(item) = items.iter_mut().rev().find(|item| item.kind == kind) else {
+            continue;
+        };
+        match field {
+            "secure_url" => item.secure_url = resolve(content),
+            "type" => item.mime_type = Some(content.trim().to_string()),
+            "width" => item.width = content.trim().parse().ok(),
+            "height" => item.height = content.trim().parse().ok(),
+            "duration" => item.duration = content.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    items
+}
+
+/// The `<h1>`–`<h6>` headings in document order. Headings without text are skipped.
+pub fn outline(document: &Html) -> Vec<Heading> {
+    let selector = Selector::parse("h1, h2, h3, h4, h5, h6").expect("valid heading selector");
+    let anchor = Selector::parse("a[id], a[name]").expect("valid anchor selector");
+
+    document
+        .select(&selector)
+        .filter_map(|heading| {
+            let text = heading.text().collect::<Vec<_>>().join(" ");
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                return None;
+            }
+            let level = heading.value().name()[1..].parse().ok()?;
+            let id = heading.value().id().or_else(|| {
+                heading.select(&anchor).next().and_then(|a| {
+                    let a = a.value();
+                    a.id().or_else(|| a.attr("name"))
+                })
+            });
+            Some(Heading {
+                level,
+                text,
+                id: id.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Absolute `http(s)` targets of the page's `<a href>` links, without
+/// fragments and duplicates, in document order. `clean` rewrites each target
+/// before duplicates are removed.
+pub fn links(document: &Html, base: &Url, clean: impl Fn(&mut Url)) -> Vec<String> {
+    let selector = Selector::parse("a[href]").expect("valid link selector");
+    let mut seen = std::collections::HashSet::new();
+    document
+        .select(&selector)
+        .filter_map(|a| base.join(a.value().attr("href")?.trim()).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|mut url| {
+            url.set_fragment(None);
+            clean(&mut url);
+            String::from(url)
+        })
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// Absolute URL declared by `<link rel="canonical">`, if any.
+pub fn canonical(document: &Html, base: &Url) -> Option<String> {
+    let selector = Selector::parse("link[rel][href]").expect("valid canonical selector");
+    document
+        .select(&selector)
+        .find(|link| {
+            link.value().attr("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("canonical"))
+            })
+        })
+        .and_then(|link| base.join(link.value().attr("href")?.trim()).ok())
+        .map(String::from)
+}
+
+/// Directives of the page's `<meta name="robots">` tags, lowercased and in
+/// order, e.g. `noindex` or `max-snippet:50`.
+pub fn robots(document: &Html) -> Vec<String> {
+    let selector = Selector::parse("meta[name][content]").expect("valid robots selector");
+    let mut directives = Vec::new();
+    for meta in document.select(&selector) {
+        let meta = meta.value();
+        if !meta
+            .attr("name")
+            .is_some_and(|name| name.trim().eq_ignore_ascii_case("robots"))
+        {
+            continue;
+        }
+        for directive in meta.attr("content").unwrap_or_default().split(',') {
+            let directive = directive.trim().to_ascii_lowercase();
+            if !directive.is_empty() && !directives.contains(&directive) {
+                directives.push(directive);
+            }
+        }
+    }
+    directives
+}
+
+/// Visible text a page may have and still count as a JavaScript shell.
+const SHELL_MAX_CHARS: usize = 500;
+
+/// Whether the page looks like the shell of an app that needs JavaScript:
+/// little visible text, and either one of `phrases` in it or in a
+/// `<noscript>`, ignoring case, or more words in `<noscript>` than outside of it.
+pub fn js_shell<'a>(document: &Html, phrases: impl IntoIterator<Item = &'a str>) -> bool {
+    let selector = Selector::parse("body").expect("valid body selector");
+    let Some(body) = document.select(&selector).next() else {
+        return false;
+    };
+    let mut visible = String::new();
+    let mut noscript = String::new();
+    for node in body.descendants() {
+        let Some(text) = node.value().as_text() else {
+            continue;
+        };
+        let mut target = Some(&mut visible);
+        for ancestor in node.ancestors().filter_map(ElementRef::wrap) {
+            match ancestor.value().name() {
+                "script" | "style" | "template" => target = None,
+                "noscript" if target.is_some() => target = Some(&mut noscript),
+                _ => {}
+            }
+        }
+        if let Some(target) = target {
+            target.push_str(text);
+            target.push(' ');
+        }
+    }
+    let visible = visible.split_whitespace().collect::<Vec<_>>().join(" ");
+    if visible.chars().count() > SHELL_MAX_CHARS {
+        return false;
+    }
+    // With scripting enabled, `<noscript>` is parsed as raw markup, e.g. the
+    // `<img>` of a tracking pixel, which is not text a reader would see.
+    let noscript = strip_tags(&noscript);
+    let text = format!("{} {}", visible, noscript).to_lowercase();
+    phrases
+        .into_iter()
+        .any(|phrase| text.contains(&phrase.to_lowercase()))
+        || noscript.split_whitespace().count() > visible.split_whitespace().count()
+}
+
+fn strip_tags(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Absolute URL of the page's icon: `<link rel="icon">`, then
+/// `apple-touch-icon`, then `/favicon.ico` at the site root.
+pub fn favicon(document: &Html, base: &Url) -> Option<String> {
+    let selector = Selector::parse("link[rel][href]").expect("valid favicon selector");
+    let links: Vec<_> = document
+        .select(&selector)
+        .filter_map(|link| {
+            let rel = link.value().attr("rel")?.to_ascii_lowercase();
+            let href = link.value().attr("href")?.trim();
+            Some((rel, href))
+        })
+        .collect();
+
+    let href_for = |name: &str| {
+        links
+            .iter()
+            .find(|(rel, _)| rel.split_whitespace().any(|r| r == name))
+            .and_then(|(_, href)| base.join(href).ok())
+    };
+
+    href_for("icon")
+        .or_else(|| href_for("apple-touch-icon"))
+        .or_else(|| base.join("/favicon.ico").ok())
+        .map(String::from)
+}
+
+/// Detaches every element matching `selector` and returns how many there were.
+pub fn remove(document: &mut Html, selector: &Selector) -> usize {
+    let ids: Vec<_> = document.select(selector).map(|e| e.id()).collect();
+    for id in &ids {
+        document.remove_node(*id);
+    }
+    ids.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_ld_scripts() {
+        let page = Html::parse_document(
+            r#"<head>
+            <script type="application/ld+json">
+            {"@context": "https://schema.org", "@type": "Article", "headline": "Hello"}
+            </script>
+            <script type="application/ld+json">{not json}</script>
+            <script>{"@type": "Ignored"}</script>
+            </head>"#,
+        );
+        let items = json_ld(&page);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["@type"], "Article");
+        assert_eq!(items[0]["headline"], "Hello");
+    }
+
+    const PHRASES: [&str; 2] = ["enable javascript", "requires javascript"];
+
+    #[test]
+    fn detects_js_shells() {
+        let shell = Html::parse_document(
+            r#"<html><head><title>App</title></head><body>
+            <noscript>You need to enable JavaScript to run this app.</noscript>
+            <div id="root"></div><script src="/static/js/main.js"></script></body></html>"#,
+        );
+        assert!(js_shell(&shell, PHRASES));
+        let noscript_only = Html::parse_document(
+            "<body><div id=app></div><noscript>This site works best with scripts \
+             turned on in your browser settings</noscript></body>",
+        );
+        assert!(js_shell(&noscript_only, []));
+    }
+
+    #[test]
+    fn ignores_pages_with_content() {
+        let article = Html::parse_document(&format!(
+            "<body><p>{}</p><noscript>Please enable JavaScript for comments.</noscript></body>",
+            "Plenty of article text. ".repeat(30)
+        ));
+        assert!(!js_shell(&article, PHRASES));
+        // The tracking pixel in `<noscript>` is markup, not words.
+        let pixel = Html::parse_document(
+            r#"<body><p>Short page.</p><noscript><img height="1" width="1"
+            src="https://www.facebook.com/tr?id=1&ev=PageView"/></noscript></body>"#,
+        );
+        assert!(!js_shell(&pixel, PHRASES));
+        let script = Html::parse_document(
+            "<body><p>Hi</p><script>const msg = 'enable javascript';</script></body>",
+        );
+        assert!(!js_shell(&script, PHRASES));
+    }
+}
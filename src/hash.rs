@@ -0,0 +1,164 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use spider::url::Url;
+use uuid::Uuid;
+
+/// Algorithm of the content hash stored with every crawled page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// SHA-256, 64 hex digits.
+    #[default]
+    Sha256,
+    /// XXH64 with seed 0, 16 hex digits. Much faster, but not collision resistant.
+    Xxhash,
+}
+
+impl HashAlgorithm {
+    /// Hex encoded hash of `content`.
+    pub fn digest(self, content: &str) -> String {
+        match self {
+            HashAlgorithm::Sha256 => hex::encode(Sha256::digest(content.as_bytes())),
+            HashAlgorithm::Xxhash => format!("{:016x}", xxh64(content.as_bytes())),
+        }
+    }
+}
+
+/// UUIDv5 (RFC 9562) in the URL namespace of `source` with its fragment
+/// removed and scheme, host and port normalized, so `HTTPS://Example.com:443/a#top`
+/// and `https://example.com/a` share an id. URLs that do not parse are used
+/// as they are. A `namespace` is prepended with a newline, so the same page
+/// gets a different id in every namespace.
+pub fn document_id(source: &str, namespace: Option<&str>) -> Uuid {
+    let url = Url::parse(source.trim())
+        .map(|mut url| {
+            url.set_fragment(None);
+            url.to_string()
+        })
+        .unwrap_or_else(|_| source.to_string());
+    let name = match namespace {
+        Some(namespace) => format!("{}\n{}", namespace, url),
+        None => url,
+    };
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes())
+}
+
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+/// XXH64 as specified by the xxHash reference, with seed 0.
+fn xxh64(input: &[u8]) -> u64 {
+    let lane = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().expect("8 byte lane"));
+    let round = |acc: u64, lane: u64| {
+        acc.wrapping_add(lane.wrapping_mul(PRIME_2))
+            .rotate_left(31)
+            .wrapping_mul(PRIME_1)
+    };
+
+    let mut stripes = input.chunks_exact(32);
+    let mut hash = if input.len() >= 32 {
+        let mut acc = [
+            PRIME_1.wrapping_add(PRIME_2),
+            PRIME_2,
+            0,
+            0u64.wrapping_sub(PRIME_1),
+        ];
+        for stripe in &mut stripes {
+            for (acc, bytes) in acc.iter_mut().zip(stripe.chunks_exact(8)) {
+                *acc = round(*acc, lane(bytes));
+            }
+        }
+        let mut hash = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        for acc in acc {
+            hash = (hash ^ round(0, acc))
+                .wrapping_mul(PRIME_1)
+                .wrapping_add(PRIME_4);
+        }
+        hash
+    } else {
+        PRIME_5
+    };
+    hash = hash.wrapping_add(input.len() as u64);
+
+    let mut rest = stripes.remainder();
+    while rest.len() >= 8 {
+        hash = (hash ^ round(0, lane(&rest[..8])))
+            .rotate_left(27)
+            .wrapping_mul(PRIME_1)
+            .wrapping_add(PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        let word = u32::from_le_bytes(rest[..4].try_into().expect("4 byte word"));
+        hash = (hash ^ u64::from(word).wrapping_mul(PRIME_1))
+            .rotate_left(23)
+            .wrapping_mul(PRIME_2)
+            .wrapping_add(PRIME_3);
+        rest = &rest[4..];
+    }
+    for byte in rest {
+        hash = (hash ^ u64::from(*byte).wrapping_mul(PRIME_5))
+            .rotate_left(11)
+            .wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xxh64_matches_the_reference() {
+        let vectors: [(&[u8], u64); 7] = [
+            (b"", 0xEF46_DB37_51D8_E999),
+            (b"a", 0xD24E_C4F1_A98C_6E5B),
+            (b"abc", 0x44BC_2CF5_AD77_0999),
+            (b"message digest", 0x066E_D728_FCEE_B3BE),
+            (b"abcdefghijklmnopqrstuvwxyz", 0xCFE1_F278_FA89_835C),
+            (
+                b"Nobody inspects the spammish repetition",
+                0xFBCE_A83C_8A37_8BF1,
+            ),
+            (
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                0xE04A_477F_19EE_145D,
+            ),
+        ];
+        for (input, expected) in vectors {
+            assert_eq!(
+                xxh64(input),
+                expected,
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+
+    #[test]
+    fn document_ids_are_stable() {
+        let id = document_id("http://127.0.0.1:8003/dated.html", None);
+        assert_eq!(id.to_string(), "466fafbc-c999-5165-a6ce-030afbb062cf");
+        assert_eq!(id.get_version_num(), 5);
+        assert_eq!(
+            document_id("http://127.0.0.1:8003/dated.html#top", None),
+            id
+        );
+        assert_ne!(
+            document_id("http://127.0.0.1:8003/dated.html", Some("project-a")),
+            id
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use moka::future::Cache;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use utoipa::ToSchema;
+
+/// Snapshot of a background job as returned by `GET /jobs/{id}`.
+#[derive(Serialize, ToSchema)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: &'static str,
+    pub status: JobStatus,
+    pub created_at: String,
+    pub total: usize,
+    pub completed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cache_hits: usize,
+}
+
+/// A page of a job's results as returned by `GET /jobs/{id}/results`.
+#[derive(Serialize, ToSchema)]
+pub struct JobResults<R> {
+    pub status: JobStatus,
+    /// Results recorded so far, in the order the crawls finished.
+    pub total: usize,
+    pub offset: usize,
+    /// Offset of the next page, unset when no further results are recorded yet.
+    pub next_offset: Option<usize>,
+    #[schema(value_type = Vec<R>)]
+    pub results: Vec<Arc<R>>,
+}
+
+#[derive(Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for one of the `APP_MAX_CONCURRENT_JOBS` slots.
+    Queued,
+    Running,
+    Completed,
+}
+
+/// Progress and results of a background job, updated by its crawl tasks.
+pub struct Job<R> {
+    id: String,
+    kind: &'static str,
+    created_at: String,
+    total: usize,
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+    cache_hits: AtomicUsize,
+    /// Only appended to, so offsets into it stay valid while the job runs.
+    results: Mutex<Vec<Arc<R>>>,
+    started: AtomicBool,
+    /// Room taken in the job limit until the job finishes, see [`Job::start`].
+    admission: Mutex<Option<Admission>>,
+}
+
+struct Admission {
+    slot: OwnedSemaphorePermit,
+    /// Taken right away if a job slot was free, else by [`Job::start`].
+    running: Option<OwnedSemaphorePermit>,
+    limit: Arc<Semaphore>,
+}
+
+/// Held while a job runs; dropping it makes room for the next one.
+pub struct JobPermit {
+    _slot: Option<OwnedSemaphorePermit>,
+    _running: Option<OwnedSemaphorePermit>,
+}
+
+impl<R> Job<R> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Waits until the job may run, i.e. fewer than the maximum number of
+    /// jobs are running, and marks it as started.
+    pub async fn start(&self) -> JobPermit {
+        let admission = self
+            .admission
+            .lock()
+            .expect("job admission poisoned")
+            .take();
+        let permit = match admission {
+            Some(Admission {
+                slot,
+                running,
+                limit,
+            }) => JobPermit {
+                _slot: Some(slot),
+                _running: match running {
+                    Some(running) => Some(running),
+                    None => limit.acquire_owned().await.ok(),
+                },
+            },
+            None => JobPermit {
+                _slot: None,
+                _running: None,
+            },
+        };
+        self.started.store(true, Ordering::Relaxed);
+        permit
+    }
+
+    pub fn record_success(&self, from_cache: bool, result: R) {
+        // Recorded before counting, so a completed job has all its results.
+        self.push(result);
+        if from_cache {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        }
+        self.succeeded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self, result: R) {
+        self.push(result);
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn push(&self, result: R) {
+        self.results
+            .lock()
+            .expect("job results poisoned")
+            .push(Arc::new(result));
+    }
+
+    /// Up to `limit` results starting at `offset`.
+    pub fn results(&self, offset: usize, limit: usize) -> JobResults<R> {
+        let status = self.info().status;
+        let results = self.results.lock().expect("job results poisoned");
+        let page: Vec<_> = results.iter().skip(offset).take(limit).cloned().collect();
+        let end = offset.saturating_add(page.len());
+        JobResults {
+            status,
+            total: results.len(),
+            offset,
+            next_offset: (end < results.len()).then_some(end),
+            results: page,
+        }
+    }
+
+    pub fn info(&self) -> JobInfo {
+        let succeeded = self.succeeded.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        let completed = succeeded + failed;
+        JobInfo {
+            id: self.id.clone(),
+            kind: self.kind,
+            status: if !self.started.load(Ordering::Relaxed) {
+                JobStatus::Queued
+            } else if completed >= self.total {
+                JobStatus::Completed
+            } else {
+                JobStatus::Running
+            },
+            created_at: self.created_at.clone(),
+            total: self.total,
+            completed,
+            succeeded,
+            failed,
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The job limit is reached and its queue is full.
+#[derive(Debug)]
+pub struct JobsFull;
+
+/// Bounds the jobs running at the same time and those waiting to run.
+struct JobLimit {
+    running: Arc<Semaphore>,
+    /// Running plus queued jobs.
+    slots: Arc<Semaphore>,
+}
+
+/// Registry of background jobs. Jobs are forgotten, along with their results,
+/// `retention` after creation.
+pub struct Jobs<R> {
+    jobs: Cache<String, Arc<Job<R>>>,
+    limit: Option<Arc<JobLimit>>,
+}
+
+// Derived `Clone` would require `R: Clone`.
+impl<R> Clone for Jobs<R> {
+    fn clone(&self) -> Self {
+        Jobs {
+            jobs: self.jobs.clone(),
+            limit: self.limit.clone(),
+        }
+    }
+}
+
+impl<R: Send + Sync + 'static> Jobs<R> {
+    /// Without `max_running`, every job starts right away. Otherwise up to
+    /// `queue_capacity` more jobs wait for their turn and further ones are
+    /// turned away.
+    pub fn new(retention: Duration, max_running: Option<usize>, queue_capacity: usize) -> Self {
+        Jobs {
+            jobs: Cache::builder().time_to_live(retention).build(),
+            limit: max_running.map(|max| {
+                Arc::new(JobLimit {
+                    running: Arc::new(Semaphore::new(max)),
+                    slots: Arc::new(Semaphore::new(max + queue_capacity)),
+                })
+            }),
+        }
+    }
+
+    pub async fn create(&self, kind: &'static str, total: usize) -> Result<Arc<Job<R>>, JobsFull> {
+        let admission = match &self.limit {
+            Some(limit) => Some(Admission {
+                slot: limit
+                    .slots
+                    .clone()
+                    .try_acquire_owned()
+                    .map_err(|_| JobsFull)?,
+                running: limit.running.clone().try_acquire_owned().ok(),
+                limit: limit.running.clone(),
+            }),
+            None => None,
+        };
+        let job = Arc::new(Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            created_at: jiff::Timestamp::now().to_string(),
+            total,
+            succeeded: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            cache_hits: AtomicUsize::new(0),
+            results: Mutex::new(Vec::new()),
+            started: AtomicBool::new(admission.as_ref().is_none_or(|a| a.running.is_some())),
+            admission: Mutex::new(admission),
+        });
+        self.jobs.insert(job.id.clone(), job.clone()).await;
+        Ok(job)
+    }
+
+    pub async fn get(&self, id: &str) -> Option<Arc<Job<R>>> {
+        self.jobs.get(id).await
+    }
+}
@@ -0,0 +1,260 @@
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{AcquireError, Notify, OwnedSemaphorePermit, Semaphore, SemaphorePermit};
+use utoipa::ToSchema;
+
+/// Caps the number of concurrent crawls per host. A host's semaphore lives as
+/// long as a permit for it is held, so idle hosts cost nothing.
+pub struct HostLimiter {
+    per_host: usize,
+    hosts: Mutex<HashMap<String, Weak<Semaphore>>>,
+}
+
+impl HostLimiter {
+    pub fn new(per_host: usize) -> Self {
+        HostLimiter {
+            per_host,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn acquire(&self, host: &str) -> OwnedSemaphorePermit {
+        let semaphore = {
+            let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+            match hosts.get(host).and_then(Weak::upgrade) {
+                Some(semaphore) => semaphore,
+                None => {
+                    hosts.retain(|_, semaphore| semaphore.strong_count() > 0);
+                    let semaphore = Arc::new(Semaphore::new(self.per_host));
+                    hosts.insert(host.to_string(), Arc::downgrade(&semaphore));
+                    semaphore
+                }
+            }
+        };
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("host semaphores are never closed")
+    }
+}
+
+/// Scheduling class of a crawl waiting for a global crawl slot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    fn level(self) -> u32 {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+/// Hands out the permits of the global crawl semaphore by priority instead of
+/// arrival. A waiting crawl rises one level every `aging`, so queued
+/// low-priority work is delayed but never starved. Running crawls are never
+/// interrupted.
+pub struct PriorityQueue {
+    semaphore: Arc<Semaphore>,
+    aging: Duration,
+    /// Waiting crawls ordered by their virtual arrival: the actual arrival
+    /// plus `aging` per level below `High`, then a sequence number.
+    waiting: Mutex<BTreeSet<(Instant, u64)>>,
+    next_seq: AtomicU64,
+    changed: Notify,
+}
+
+impl PriorityQueue {
+    pub fn new(semaphore: Arc<Semaphore>, aging: Duration) -> Self {
+        PriorityQueue {
+            semaphore,
+            aging,
+            waiting: Mutex::new(BTreeSet::new()),
+            next_seq: AtomicU64::new(0),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Waits until all crawls ahead of this one got a permit, then for the
+    /// permit itself. Only the first crawl waits on the semaphore and steps
+    /// back when one ahead of it arrives.
+    pub async fn acquire(&self, priority: Priority) -> Result<SemaphorePermit<'_>, AcquireError> {
+        let key = (
+            Instant::now() + self.aging * priority.level(),
+            self.next_seq.fetch_add(1, Ordering::Relaxed),
+        );
+        self.lock().insert(key);
+        let _ticket = Ticket { queue: self, key };
+        self.changed.notify_waiters();
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            if self.lock().first() == Some(&key) {
+                tokio::select! {
+                    permit = self.semaphore.acquire() => return permit,
+                    _ = &mut changed => {}
+                }
+            } else {
+                changed.await;
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeSet<(Instant, u64)>> {
+        self.waiting.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Place of a crawl in the [`PriorityQueue`], given up once it has its permit
+/// or stopped waiting, e.g. because the client disconnected.
+struct Ticket<'a> {
+    queue: &'a PriorityQueue,
+    key: (Instant, u64),
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        self.queue.lock().remove(&self.key);
+        self.queue.changed.notify_waiters();
+    }
+}
+
+/// Total crawl time one request may spend across its URLs, see
+/// `max_total_render_seconds`.
+pub struct RenderBudget {
+    limit: Duration,
+    used_ms: AtomicU64,
+}
+
+impl RenderBudget {
+    pub fn new(limit: Duration) -> Self {
+        RenderBudget {
+            limit,
+            used_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn exhausted(&self) -> bool {
+        Duration::from_millis(self.used_ms.load(Ordering::Relaxed)) >= self.limit
+    }
+
+    /// Charges the time until the returned guard is dropped to the budget.
+    pub fn start(&self) -> BudgetCharge<'_> {
+        BudgetCharge {
+            budget: self,
+            started_at: Instant::now(),
+        }
+    }
+}
+
+pub struct BudgetCharge<'a> {
+    budget: &'a RenderBudget,
+    started_at: Instant,
+}
+
+impl Drop for BudgetCharge<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.started_at.elapsed().as_millis() as u64;
+        self.budget.used_ms.fetch_add(elapsed, Ordering::Relaxed);
+    }
+}
+
+/// Ramps the global crawl limit up after idle periods so a cold Chrome is not
+/// hit with the full concurrency at once. Permits above the current limit are
+/// taken out of the semaphore and handed back as the ramp progresses.
+pub struct SlowStart {
+    semaphore: Arc<Semaphore>,
+    max: usize,
+    ramp: Duration,
+    idle: Duration,
+    withheld: usize,
+    phase: Phase,
+}
+
+#[derive(Clone, Copy)]
+enum Phase {
+    /// Limited to one crawl until activity resumes.
+    Cold,
+    Ramping {
+        started_at: Instant,
+    },
+    Warm {
+        idle_since: Option<Instant>,
+    },
+}
+
+impl SlowStart {
+    const TICK: Duration = Duration::from_millis(250);
+
+    /// Starts cold and adjusts `semaphore`, which holds `max` permits, in the
+    /// background from then on.
+    pub fn spawn(semaphore: Arc<Semaphore>, max: usize, ramp: Duration, idle: Duration) {
+        let mut slow_start = SlowStart {
+            semaphore,
+            max,
+            ramp,
+            idle,
+            withheld: 0,
+            phase: Phase::Warm { idle_since: None },
+        };
+        slow_start.cool_down();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Self::TICK);
+            loop {
+                interval.tick().await;
+                slow_start.tick(Instant::now());
+            }
+        });
+    }
+
+    fn cool_down(&mut self) {
+        self.withheld += self.semaphore.forget_permits(self.max - 1 - self.withheld);
+        self.phase = Phase::Cold;
+    }
+
+    fn tick(&mut self, now: Instant) {
+        let in_use = self.max - self.withheld - self.semaphore.available_permits();
+        match self.phase {
+            Phase::Cold if in_use > 0 => self.phase = Phase::Ramping { started_at: now },
+            Phase::Cold => {}
+            Phase::Ramping { started_at } => {
+                // Linear from 1 to `max` over `ramp`.
+                let progress = (now - started_at).as_secs_f64() / self.ramp.as_secs_f64();
+                let limit = 1 + ((self.max - 1) as f64 * progress.min(1.0)) as usize;
+                let withheld = self.max - limit;
+                if withheld < self.withheld {
+                    self.semaphore.add_permits(self.withheld - withheld);
+                    self.withheld = withheld;
+                }
+                if self.withheld == 0 {
+                    self.phase = Phase::Warm { idle_since: None };
+                }
+            }
+            Phase::Warm { .. } if in_use > 0 => self.phase = Phase::Warm { idle_since: None },
+            Phase::Warm { idle_since: None } => {
+                self.phase = Phase::Warm {
+                    idle_since: Some(now),
+                }
+            }
+            Phase::Warm {
+                idle_since: Some(since),
+            } => {
+                if now - since >= self.idle {
+                    self.cool_down();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use log::{info, warn};
+use publicsuffix::{List, Psl};
+use serde::{Deserialize, Serialize};
+use spider::url::Url;
+use utoipa::ToSchema;
+
+/// Which links count as internal.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkScope {
+    /// Same registrable domain, so `blog.example.com` is internal to `www.example.com`.
+    Domain,
+    /// Same host name only.
+    Host,
+}
+
+/// Links of a page returned by `include_links`.
+#[derive(Clone, Default, Serialize, ToSchema)]
+pub struct Links {
+    pub internal: Vec<String>,
+    pub external: Vec<String>,
+}
+
+/// Decides whether two URLs belong to the same site.
+pub struct SiteMatcher {
+    scope: LinkScope,
+    /// Public suffix list for [`LinkScope::Domain`]; without it, hosts are compared.
+    list: Option<List>,
+}
+
+impl SiteMatcher {
+    /// Reads the public suffix list at `path` when `scope` needs it. A missing
+    /// or invalid list falls back to comparing hosts instead of failing startup.
+    pub fn load(scope: LinkScope, path: &str) -> Self {
+        if scope == LinkScope::Host {
+            return SiteMatcher { scope, list: None };
+        }
+        let list = match std::fs::read(path) {
+            Ok(bytes) => List::from_bytes(&bytes)
+                .inspect(|_| info!("Loaded public suffix list from {}", path))
+                .inspect_err(|e| warn!("Invalid public suffix list {}: {}", path, e))
+                .ok(),
+            Err(e) => {
+                warn!("Cannot read public suffix list {}: {}", path, e);
+                None
+            }
+        };
+        if list.is_none() {
+            warn!("Classifying links by host instead of registrable domain");
+        }
+        SiteMatcher { scope, list }
+    }
+
+    /// Splits `links` into those on the same site as `page` and the rest.
+    pub fn classify(&self, page: &Url, links: Vec<String>) -> Links {
+        let site = page.host_str().map(|host| self.site(host));
+        let mut result = Links::default();
+        for link in links {
+            let same_site = Url::parse(&link)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| self.site(host)))
+                .is_some_and(|link_site| Some(link_site) == site);
+            if same_site {
+                result.internal.push(link);
+            } else {
+                result.external.push(link);
+            }
+        }
+        result
+    }
+
+    fn site(&self, host: &str) -> String {
+        let host = host.to_ascii_lowercase();
+        match (&self.list, self.scope) {
+            (Some(list), LinkScope::Domain) => list
+                .domain(host.as_bytes())
+                .and_then(|domain| std::str::from_utf8(domain.as_bytes()).ok())
+                .map(str::to_string)
+                .unwrap_or(host),
+            _ => host,
+        }
+    }
+}
+
+/// Query parameters removed by `strip_tracking_params`, from a comma-separated
+/// list such as `utm_*,fbclid`. Entries ending in `*` match by prefix and
+/// names are compared ignoring case.
+pub struct TrackingParams(Vec<String>);
+
+impl TrackingParams {
+    pub fn parse(list: &str) -> Self {
+        TrackingParams(
+            list.split(',')
+                .map(|name| name.trim().to_ascii_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect(),
+        )
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.0.iter().any(|param| match param.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *param,
+        })
+    }
+
+    /// Drops the tracking parameters from `url`'s query, leaving the others
+    /// exactly as they were encoded.
+    pub fn strip(&self, url: &mut Url) {
+        let Some(query) = url.query() else {
+            return;
+        };
+        let pairs: Vec<&str> = query.split('&').collect();
+        let kept: Vec<&str> = pairs
+            .iter()
+            .copied()
+            .filter(|pair| !self.matches(pair.split('=').next().unwrap_or_default()))
+            .collect();
+        if kept.len() == pairs.len() {
+            return;
+        }
+        let query = kept.join("&");
+        url.set_query((!query.is_empty()).then_some(query.as_str()));
+    }
+}
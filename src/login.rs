@@ -0,0 +1,106 @@
+use scraper::Selector;
+use serde::{Deserialize, Serialize};
+use spider::features::chrome_common::WebAutomation;
+use spider::url::Url;
+use utoipa::ToSchema;
+
+/// Time the login form and the `wait_for_selector` element may take to appear.
+const SELECTOR_TIMEOUT_MS: u64 = 10_000;
+
+/// Signs in through a login form before the page is crawled. The form is
+/// filled in the same Chrome tab, which then navigates to the page, so the
+/// session cookies set by the login apply to it.
+#[derive(Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct LoginFlow {
+    /// Page with the login form.
+    #[schema(example = "https://example.com/login")]
+    pub url: String,
+    #[schema(example = "input[name=username]")]
+    pub username_selector: String,
+    #[schema(example = "input[type=password]")]
+    pub password_selector: String,
+    #[schema(example = "button[type=submit]")]
+    pub submit_selector: String,
+    pub username: String,
+    /// Never serialized, so it stays out of cache keys and logs.
+    #[serde(skip_serializing)]
+    pub password: String,
+    /// Element to wait for on the page after signing in.
+    #[schema(example = "#dashboard")]
+    pub wait_for_selector: Option<String>,
+}
+
+impl LoginFlow {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = Url::parse(&self.url)
+            .map_err(|e| format!("invalid login url '{}': {}", self.url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("login url must be http or https: {}", self.url));
+        }
+        let selectors = [
+            ("username_selector", Some(&self.username_selector)),
+            ("password_selector", Some(&self.password_selector)),
+            ("submit_selector", Some(&self.submit_selector)),
+            ("wait_for_selector", self.wait_for_selector.as_ref()),
+        ];
+        for (name, selector) in selectors {
+            if let Some(selector) = selector {
+                Selector::parse(selector)
+                    .map_err(|e| format!("invalid login {} '{}': {:?}", name, selector, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Automation run on the login page: fills and submits the form, then
+    /// navigates to `target`. A step that fails ends the chain, leaving the
+    /// tab on the login form.
+    pub fn steps(&self, target: &str) -> Vec<WebAutomation> {
+        let mut steps = vec![
+            WebAutomation::WaitForWithTimeout {
+                selector: self.username_selector.clone(),
+                timeout: SELECTOR_TIMEOUT_MS,
+            },
+            WebAutomation::ValidateChain,
+            WebAutomation::Fill {
+                selector: self.username_selector.clone(),
+                value: self.username.clone(),
+            },
+            WebAutomation::ValidateChain,
+            WebAutomation::Fill {
+                selector: self.password_selector.clone(),
+                value: self.password.clone(),
+            },
+            WebAutomation::ValidateChain,
+            WebAutomation::Click(self.submit_selector.clone()),
+            WebAutomation::ValidateChain,
+            WebAutomation::WaitForNavigation,
+            WebAutomation::Evaluate(format!(
+                "window.location.href = {}",
+                serde_json::Value::from(target)
+            )),
+            WebAutomation::WaitForNavigation,
+            WebAutomation::WaitForDom {
+                selector: Some("body".to_string()),
+                timeout: 5000,
+            },
+        ];
+        if let Some(selector) = &self.wait_for_selector {
+            steps.push(WebAutomation::WaitForWithTimeout {
+                selector: selector.clone(),
+                timeout: SELECTOR_TIMEOUT_MS,
+            });
+        }
+        steps
+    }
+
+    /// Whether `html` still shows the login form, i.e. signing in failed.
+    pub fn form_remains(&self, html: &str) -> bool {
+        Selector::parse(&self.password_selector).is_ok_and(|selector| {
+            scraper::Html::parse_document(html)
+                .select(&selector)
+                .next()
+                .is_some()
+        })
+    }
+}
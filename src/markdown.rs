@@ -0,0 +1,152 @@
+use regex::{Captures, Regex};
+use serde::Serialize;
+use spider::url::Url;
+use std::sync::LazyLock;
+use utoipa::ToSchema;
+
+/// Part of the content from one heading up to the next, returned by `include_sections`.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Section {
+    /// Heading text without the `#` markers; `null` for content before the first heading.
+    pub heading: Option<String>,
+    /// 1 for `#` through 6 for `######`, 0 for content before the first heading.
+    pub level: u8,
+    /// Markdown between this heading and the next one of any level.
+    pub content: String,
+}
+
+/// Returns the level of an ATX heading line (`## Title`), if it is one.
+fn heading_level(line: &str) -> Option<usize> {
+    let hashes = line.bytes().take_while(|b| *b == b'#').count();
+    let rest = &line[hashes..];
+    let is_heading = (1..=6).contains(&hashes) && (rest.is_empty() || rest.starts_with(' '));
+    is_heading.then_some(hashes)
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// Rewrites ATX headings so the top-most level becomes `#` and the levels in
+/// use are contiguous, e.g. `###`/`#####` become `#`/`##`. Headings inside
+/// fenced code blocks are left alone.
+///
+/// Returns the rewritten markdown and whether anything changed.
+pub fn normalize_headings(markdown: &str) -> (String, bool) {
+    let mut levels = Vec::new();
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        } else if !in_fence && let Some(level) = heading_level(line) {
+            levels.push(level);
+        }
+    }
+    levels.sort_unstable();
+    levels.dedup();
+
+    let target = |level: usize| levels.iter().position(|l| *l == level).unwrap_or(0) + 1;
+    if levels.iter().all(|level| target(*level) == *level) {
+        return (markdown.to_string(), false);
+    }
+
+    let mut output = String::with_capacity(markdown.len());
+    let mut in_fence = false;
+    for line in markdown.split_inclusive('\n') {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        } else if !in_fence && let Some(level) = heading_level(line.trim_end_matches(['\r', '\n']))
+        {
+            output.push_str(&"#".repeat(target(level)));
+            output.push_str(&line[level..]);
+            continue;
+        }
+        output.push_str(line);
+    }
+
+    (output, true)
+}
+
+/// Splits `markdown` at its ATX headings outside fenced code blocks. Content
+/// before the first heading becomes a section without a heading at level 0,
+/// left out when it is blank. Nested headings start sections of their own, so
+/// a section's content never includes that of its subsections.
+pub fn sections(markdown: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut current = Section {
+        heading: None,
+        level: 0,
+        content: String::new(),
+    };
+    let mut in_fence = false;
+    for line in markdown.split_inclusive('\n') {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            let trimmed = line.trim_end_matches(['\r', '\n']);
+            if let Some(level) = heading_level(trimmed) {
+                let text = trimmed[level..].trim();
+                // A closing sequence (`## Title ##`) is not part of the text, a
+                // `#` ending a word (`C#`) is.
+                let text = match text.trim_end_matches('#') {
+                    rest if rest.is_empty() || rest.ends_with(' ') => rest.trim_end(),
+                    _ => text,
+                };
+                let next = Section {
+                    heading: Some(text.to_string()),
+                    level: level as u8,
+                    content: String::new(),
+                };
+                push_section(&mut sections, std::mem::replace(&mut current, next));
+                continue;
+            }
+        }
+        current.content.push_str(line);
+    }
+    push_section(&mut sections, current);
+    sections
+}
+
+fn push_section(sections: &mut Vec<Section>, mut section: Section) {
+    section.content = section.content.trim_matches(['\r', '\n']).to_string();
+    if section.heading.is_some() || !section.content.trim().is_empty() {
+        sections.push(section);
+    }
+}
+
+/// `[text](target "title")` and `![alt](target)`; the target is group 1.
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\]\(([^)\s<>]+)((?:\s+"[^"]*")?)\)"#).expect("valid link regex")
+});
+
+/// Resolves relative link and image targets against `base`. Targets with a
+/// scheme (including `data:` and `mailto:`) and in-page anchors (`#top`) are
+/// kept as they are, as are links inside fenced code blocks.
+pub fn absolutize_links(markdown: &str, base: &Url) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    let mut in_fence = false;
+    for line in markdown.split_inclusive('\n') {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence || !line.contains("](") {
+            output.push_str(line);
+            continue;
+        }
+        let line = LINK.replace_all(line, |caps: &Captures| {
+            let target = &caps[1];
+            let resolved = if target.starts_with('#') || Url::parse(target).is_ok() {
+                None
+            } else {
+                base.join(target).ok()
+            };
+            match resolved {
+                Some(url) => format!("]({}{})", url, &caps[2]),
+                None => caps[0].to_string(),
+            }
+        });
+        output.push_str(&line);
+    }
+    output
+}
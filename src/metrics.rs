@@ -0,0 +1,224 @@
+use moka::notification::RemovalCause;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+/// Process-wide counters exposed by `GET /metrics` in the Prometheus text format.
+#[derive(Default)]
+pub struct Metrics {
+    evictions_replaced: AtomicU64,
+    evictions_size: AtomicU64,
+    evictions_expired: AtomicU64,
+    evictions_explicit: AtomicU64,
+    /// Distinct tenants labelled in `crawls` before the rest count as `other`.
+    max_tenants: usize,
+    /// Crawled URLs by tenant and outcome (`success` or `failure`).
+    crawls: Mutex<BTreeMap<(String, &'static str), u64>>,
+    /// Crawls holding a concurrency permit.
+    crawls_in_flight: AtomicI64,
+    /// Crawls waiting for a host or global concurrency permit.
+    crawls_queued: AtomicI64,
+    /// Crawls failed by the Chrome DevTools protocol rather than the site.
+    browser_errors: AtomicU64,
+    /// Requests answered with `503` because the crawl queue was full.
+    queue_rejections: AtomicU64,
+    /// Job callbacks that still failed after their retries.
+    callback_failures: AtomicU64,
+}
+
+/// Decrements its gauge when dropped.
+pub struct GaugeGuard<'a>(&'a AtomicI64);
+
+impl<'a> GaugeGuard<'a> {
+    fn new(gauge: &'a AtomicI64) -> Self {
+        gauge.fetch_add(1, Ordering::Relaxed);
+        GaugeGuard(gauge)
+    }
+}
+
+impl Drop for GaugeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub fn new(max_tenants: usize) -> Self {
+        Metrics {
+            max_tenants,
+            ..Default::default()
+        }
+    }
+
+    /// Called from the cache's eviction listener, so it must stay cheap.
+    pub fn record_eviction(&self, cause: RemovalCause) {
+        let counter = match cause {
+            RemovalCause::Replaced => &self.evictions_replaced,
+            RemovalCause::Size => &self.evictions_size,
+            RemovalCause::Expired => &self.evictions_expired,
+            RemovalCause::Explicit => &self.evictions_explicit,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_crawl(&self, tenant: Option<&str>, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        let mut crawls = self.crawls.lock().unwrap_or_else(|e| e.into_inner());
+        let tenant = tenant.unwrap_or("default");
+        // Keys are sorted by tenant, so adjacent duplicates are its outcomes.
+        let mut tenants: Vec<&str> = crawls.keys().map(|(t, _)| t.as_str()).collect();
+        tenants.dedup();
+        let tenant = if tenants.contains(&tenant) || tenants.len() < self.max_tenants {
+            tenant
+        } else {
+            "other"
+        };
+        *crawls.entry((tenant.to_string(), outcome)).or_default() += 1;
+    }
+
+    pub fn record_browser_error(&self) {
+        self.browser_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_queue_rejection(&self) {
+        self.queue_rejections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_callback_failure(&self) {
+        self.callback_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a crawl as queued until the guard is dropped.
+    pub fn track_queued(&self) -> GaugeGuard<'_> {
+        GaugeGuard::new(&self.crawls_queued)
+    }
+
+    /// Counts a crawl as in flight until the guard is dropped.
+    pub fn track_in_flight(&self) -> GaugeGuard<'_> {
+        GaugeGuard::new(&self.crawls_in_flight)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "spider_cache_evictions_total",
+            "counter",
+            "Cache entries removed, by cause.",
+        );
+        for (cause, counter) in [
+            ("replaced", &self.evictions_replaced),
+            ("size", &self.evictions_size),
+            ("expired", &self.evictions_expired),
+            ("explicit", &self.evictions_explicit),
+        ] {
+            sample(
+                &mut out,
+                "spider_cache_evictions_total",
+                &[("cause", cause)],
+                counter.load(Ordering::Relaxed),
+            );
+        }
+
+        header(
+            &mut out,
+            "spider_crawls_total",
+            "counter",
+            "Crawled URLs, by tenant and outcome.",
+        );
+        let crawls = self.crawls.lock().unwrap_or_else(|e| e.into_inner());
+        for ((tenant, outcome), count) in crawls.iter() {
+            sample(
+                &mut out,
+                "spider_crawls_total",
+                &[("tenant", tenant), ("outcome", outcome)],
+                count,
+            );
+        }
+        drop(crawls);
+
+        header(
+            &mut out,
+            "spider_browser_errors_total",
+            "counter",
+            "Crawls failed by a Chrome protocol error, e.g. a crashed tab.",
+        );
+        sample(
+            &mut out,
+            "spider_browser_errors_total",
+            &[],
+            self.browser_errors.load(Ordering::Relaxed),
+        );
+
+        header(
+            &mut out,
+            "spider_queue_rejections_total",
+            "counter",
+            "Requests rejected with 503 because the crawl queue was full.",
+        );
+        sample(
+            &mut out,
+            "spider_queue_rejections_total",
+            &[],
+            self.queue_rejections.load(Ordering::Relaxed),
+        );
+
+        header(
+            &mut out,
+            "spider_callback_failures_total",
+            "counter",
+            "Job callbacks not delivered, retries included.",
+        );
+        sample(
+            &mut out,
+            "spider_callback_failures_total",
+            &[],
+            self.callback_failures.load(Ordering::Relaxed),
+        );
+
+        for (name, help, gauge) in [
+            (
+                "spider_crawls_in_flight",
+                "Crawls currently running.",
+                &self.crawls_in_flight,
+            ),
+            (
+                "spider_crawls_queued",
+                "Crawls waiting for a concurrency permit.",
+                &self.crawls_queued,
+            ),
+        ] {
+            header(&mut out, name, "gauge", help);
+            sample(&mut out, name, &[], gauge.load(Ordering::Relaxed));
+        }
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let _ = write!(out, "{{{}}}", labels);
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
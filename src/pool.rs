@@ -0,0 +1,77 @@
+use log::warn;
+use spider::chromiumoxide::cdp::browser_protocol::storage::ClearCookiesParams;
+use spider::features::chrome::BrowserController;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Chrome connections kept open between crawls, so a crawl does not have to
+/// connect and set up a browser context first. Each connection serves one
+/// crawl at a time and is only reused by crawls with the same `key`, since
+/// spider applies headers and request blocking when it opens a connection.
+pub struct BrowserPool {
+    size: usize,
+    /// Least recently used first.
+    idle: Mutex<VecDeque<(String, Arc<BrowserController>)>>,
+}
+
+impl BrowserPool {
+    /// A pool of `size` idle connections; `0` disables pooling.
+    pub fn new(size: usize) -> Self {
+        BrowserPool {
+            size,
+            idle: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.size > 0
+    }
+
+    /// An idle connection opened for `key`. Connections whose DevTools session
+    /// ended, e.g. because Chrome restarted, are evicted on the way.
+    pub fn take(&self, key: &str) -> Option<Arc<BrowserController>> {
+        let mut idle = self.idle.lock().expect("browser pool poisoned");
+        idle.retain(|(_, browser)| is_alive(browser));
+        let index = idle.iter().position(|(k, _)| k == key)?;
+        idle.remove(index).map(|(_, browser)| browser)
+    }
+
+    /// Keeps `browser` for the next crawl with `key`, after clearing the
+    /// cookies the last crawl left in its browser context. The least recently
+    /// used connection is closed to make room when the pool is full.
+    pub async fn put(&self, key: String, browser: Arc<BrowserController>) {
+        if !self.enabled() || !is_alive(&browser) {
+            return;
+        }
+        let clear = ClearCookiesParams {
+            browser_context_id: browser.browser.2.clone(),
+        };
+        if let Err(e) = browser.browser.0.execute(clear).await {
+            warn!(
+                "Closing pooled Chrome connection, clearing its cookies failed: {}",
+                e
+            );
+            return;
+        }
+        let mut idle = self.idle.lock().expect("browser pool poisoned");
+        if idle.len() >= self.size {
+            idle.pop_front();
+        }
+        idle.push_back((key, browser));
+    }
+
+    /// Closes every idle connection, e.g. after Chrome was lost.
+    pub fn clear(&self) {
+        self.idle.lock().expect("browser pool poisoned").clear();
+    }
+}
+
+/// Whether the connection's DevTools handler is still running.
+fn is_alive(browser: &BrowserController) -> bool {
+    !browser.closed
+        && browser
+            .browser
+            .1
+            .as_ref()
+            .is_some_and(|handler| !handler.is_finished())
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Order of the pages returned by a batch crawl.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Longest content first.
+    Length,
+    /// Highest [`relevance`] to the request's `query` first.
+    Relevance,
+}
+
+/// Naive term-frequency score: how many words of `content` equal one of the
+/// words of `query`, ignoring case. Words are runs of letters and digits.
+pub fn relevance(content: &str, query: &str) -> usize {
+    let terms: Vec<String> = words(query).collect();
+    if terms.is_empty() {
+        return 0;
+    }
+    words(content).filter(|word| terms.contains(word)).count()
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
@@ -0,0 +1,210 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use spider::url::Url;
+use utoipa::ToSchema;
+
+/// Items nested deeper than this are returned as their text.
+const MAX_DEPTH: usize = 32;
+
+/// Syntax of the structured data returned by `include_jsonld`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StructuredDataFormat {
+    /// `<script type="application/ld+json">` blocks, returned as they are.
+    Jsonld,
+    /// `itemscope`/`itemprop` items.
+    Microdata,
+    /// RDFa Lite `typeof`/`property` resources.
+    Rdfa,
+}
+
+/// Top-level microdata items, i.e. `itemscope` elements that are not a
+/// property of another item, normalized to JSON-LD style objects.
+pub fn microdata(document: &Html, base: Option<&Url>) -> Vec<Value> {
+    let selector = Selector::parse("[itemscope]:not([itemprop])").expect("valid item selector");
+    document
+        .select(&selector)
+        .map(|item| Syntax::Microdata.item(item, base, None, 0))
+        .collect()
+}
+
+/// Top-level RDFa resources, i.e. `typeof` elements that are not a property
+/// of another resource, normalized to JSON-LD style objects.
+pub fn rdfa(document: &Html, base: Option<&Url>) -> Vec<Value> {
+    let selector = Selector::parse("[typeof]:not([property])").expect("valid resource selector");
+    document
+        .select(&selector)
+        .map(|item| Syntax::Rdfa.item(item, base, None, 0))
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+enum Syntax {
+    Microdata,
+    Rdfa,
+}
+
+impl Syntax {
+    fn property_attr(self) -> &'static str {
+        match self {
+            Syntax::Microdata => "itemprop",
+            Syntax::Rdfa => "property",
+        }
+    }
+
+    fn is_item(self, element: ElementRef) -> bool {
+        let attr = match self {
+            Syntax::Microdata => "itemscope",
+            Syntax::Rdfa => "typeof",
+        };
+        element.value().attr(attr).is_some()
+    }
+
+    /// `@context`, `@type` and `@id` of the item, then its properties. Nested
+    /// items only carry `@context` if it differs from `parent`'s.
+    fn item(
+        self,
+        item: ElementRef,
+        base: Option<&Url>,
+        parent: Option<&str>,
+        depth: usize,
+    ) -> Value {
+        let element = item.value();
+        let mut object = Map::new();
+        let (context, types): (_, Vec<&str>) = match self {
+            Syntax::Microdata => {
+                // Item types are absolute URLs within a single vocabulary,
+                // e.g. `https://schema.org/Product`.
+                let types: Vec<_> = element
+                    .attr("itemtype")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect();
+                let context = types
+                    .first()
+                    .and_then(|t| t.rfind(['/', '#']).map(|i| &t[..i]));
+                let types = types
+                    .into_iter()
+                    .map(|t| {
+                        context
+                            .and_then(|c| t.strip_prefix(c)?.get(1..))
+                            .filter(|name| !name.is_empty())
+                            .unwrap_or(t)
+                    })
+                    .collect();
+                (context.map(str::to_string), types)
+            }
+            Syntax::Rdfa => {
+                let vocab =
+                    std::iter::successors(Some(item), |e| e.parent().and_then(ElementRef::wrap))
+                        .find_map(|e| e.value().attr("vocab"));
+                let types = element
+                    .attr("typeof")
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect();
+                (vocab.map(|v| v.trim_end_matches('/').to_string()), types)
+            }
+        };
+        let context = context.filter(|c| !c.is_empty());
+        if let Some(context) = context.as_deref().filter(|c| Some(*c) != parent) {
+            object.insert("@context".to_string(), Value::from(context));
+        }
+        match types.as_slice() {
+            [] => {}
+            [single] => {
+                object.insert("@type".to_string(), Value::from(*single));
+            }
+            types => {
+                object.insert("@type".to_string(), Value::from(types.to_vec()));
+            }
+        }
+        let id = match self {
+            Syntax::Microdata => element.attr("itemid"),
+            Syntax::Rdfa => element.attr("resource").or_else(|| element.attr("about")),
+        };
+        if let Some(id) = id {
+            object.insert("@id".to_string(), Value::String(resolve(id, base)));
+        }
+        self.properties(item, base, context.as_deref(), depth, &mut object);
+        Value::Object(object)
+    }
+
+    /// Adds the properties below `item`, without descending into nested items.
+    fn properties(
+        self,
+        item: ElementRef,
+        base: Option<&Url>,
+        context: Option<&str>,
+        depth: usize,
+        object: &mut Map<String, Value>,
+    ) {
+        let mut stack: Vec<_> = item.children().filter_map(ElementRef::wrap).collect();
+        stack.reverse();
+        while let Some(element) = stack.pop() {
+            let nested = self.is_item(element);
+            if let Some(names) = element.value().attr(self.property_attr()) {
+                let value = if nested && depth < MAX_DEPTH {
+                    self.item(element, base, context, depth + 1)
+                } else {
+                    Value::String(self.value(element, base))
+                };
+                for name in names.split_whitespace() {
+                    match object.get_mut(name) {
+                        Some(Value::Array(values)) => values.push(value.clone()),
+                        Some(existing) => {
+                            *existing = Value::Array(vec![existing.take(), value.clone()])
+                        }
+                        None => {
+                            object.insert(name.to_string(), value.clone());
+                        }
+                    }
+                }
+            }
+            if !nested {
+                let start = stack.len();
+                stack.extend(element.children().filter_map(ElementRef::wrap));
+                stack[start..].reverse();
+            }
+        }
+    }
+
+    /// A property value as the syntax defines it: an attribute for links,
+    /// media and machine-readable values, the element's text otherwise.
+    fn value(self, element: ElementRef, base: Option<&Url>) -> String {
+        let e = element.value();
+        let url = |attr: &str| e.attr(attr).map(|v| resolve(v, base));
+        let value = match self {
+            Syntax::Microdata => match e.name() {
+                "meta" => e.attr("content").map(str::to_string),
+                "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => url("src"),
+                "a" | "area" | "link" => url("href"),
+                "object" => url("data"),
+                "data" | "meter" => e.attr("value").map(str::to_string),
+                "time" => e.attr("datetime").map(str::to_string),
+                _ => None,
+            },
+            Syntax::Rdfa => e
+                .attr("content")
+                .map(str::to_string)
+                .or_else(|| url("href"))
+                .or_else(|| url("src"))
+                .or_else(|| e.attr("datetime").map(str::to_string)),
+        };
+        value.unwrap_or_else(|| {
+            element
+                .text()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+}
+
+fn resolve(url: &str, base: Option<&Url>) -> String {
+    base.and_then(|base| base.join(url.trim()).ok())
+        .map_or_else(|| url.to_string(), String::from)
+}
@@ -0,0 +1,109 @@
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How tables are represented in the converted content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TablesAs {
+    /// Left to the markdown conversion.
+    #[default]
+    Markdown,
+    /// Kept as raw HTML blocks.
+    Html,
+    /// Fenced `csv` code blocks, one row per line.
+    Csv,
+}
+
+fn placeholder(index: usize) -> String {
+    format!("SPIDERTABLE{}PLACEHOLDER", index)
+}
+
+/// Swaps every outermost `<table>` in `html` for a placeholder paragraph so the
+/// markdown conversion leaves it alone. Returns the rewritten HTML and the
+/// tables rendered as `format`, to be put back with [`restore`].
+pub fn extract(html: &str, format: TablesAs) -> (String, Vec<String>) {
+    if format == TablesAs::Markdown {
+        return (html.to_string(), Vec::new());
+    }
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("table").expect("valid table selector");
+    let outermost: Vec<_> = document
+        .select(&selector)
+        .filter(|table| enclosing_table(*table).is_none())
+        .collect();
+    if outermost.is_empty() {
+        return (html.to_string(), Vec::new());
+    }
+
+    let mut rewritten = document.html();
+    let mut tables = Vec::new();
+    for table in outermost {
+        let outer = table.html();
+        if !rewritten.contains(&outer) {
+            continue;
+        }
+        let marker = format!("<p>{}</p>", placeholder(tables.len()));
+        rewritten = rewritten.replacen(&outer, &marker, 1);
+        tables.push(match format {
+            TablesAs::Markdown => unreachable!("returned early"),
+            // A blank line would end the HTML block in most markdown renderers.
+            TablesAs::Html => outer
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            TablesAs::Csv => format!("```csv\n{}\n```", csv(table)),
+        });
+    }
+    (rewritten, tables)
+}
+
+/// Replaces the placeholders left by [`extract`] with the rendered tables,
+/// set apart by blank lines.
+pub fn restore(markdown: &str, tables: &[String]) -> String {
+    tables
+        .iter()
+        .enumerate()
+        .fold(markdown.to_string(), |markdown, (index, table)| {
+            markdown.replacen(&placeholder(index), &format!("\n{}\n", table), 1)
+        })
+}
+
+/// Nearest `<table>` that `element` is nested in.
+fn enclosing_table(element: ElementRef<'_>) -> Option<ElementRef<'_>> {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().name() == "table")
+}
+
+/// Rows of `table` as CSV. Cells of nested tables are flattened into the text
+/// of the cell containing them.
+fn csv(table: ElementRef<'_>) -> String {
+    let selector = Selector::parse("tr").expect("valid row selector");
+    table
+        .select(&selector)
+        .filter(|row| enclosing_table(*row).is_some_and(|t| t.id() == table.id()))
+        .map(|row| {
+            row.children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .map(|cell| {
+                    let text = cell.text().collect::<Vec<_>>().join(" ");
+                    escape(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
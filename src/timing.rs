@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use spider::features::chrome_common::WebAutomation;
+use utoipa::ToSchema;
+
+/// `type` of the element [`capture`] leaves in the DOM for [`take`] to find.
+const MARKER: &str = "application/x-spider-timing";
+
+/// Navigation timing of a page rendered in Chrome, returned by `include_timing`.
+/// Phases that did not happen, e.g. DNS for a reused connection, are left out.
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct Timing {
+    /// Domain name lookup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<u64>,
+    /// TCP connect, including the TLS handshake.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// TLS handshake alone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<u64>,
+    /// From the start of the navigation to the first byte of the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<u64>,
+    /// Receiving the response body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_ms: Option<u64>,
+    /// From the start of the navigation until `DOMContentLoaded` was handled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dom_content_loaded_ms: Option<u64>,
+    /// From the start of the navigation until the `load` event was handled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_ms: Option<u64>,
+}
+
+/// `PerformanceNavigationTiming` entry as serialized by the browser.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NavigationTiming {
+    #[serde(default)]
+    start_time: f64,
+    domain_lookup_start: f64,
+    domain_lookup_end: f64,
+    connect_start: f64,
+    connect_end: f64,
+    #[serde(default)]
+    secure_connection_start: f64,
+    response_start: f64,
+    response_end: f64,
+    dom_content_loaded_event_end: f64,
+    load_event_end: f64,
+}
+
+/// Automation step that records the page's navigation timing in the DOM,
+/// where [`take`] picks it up from the captured HTML. `<` is escaped so the
+/// serialized URL cannot end the script element.
+pub fn capture() -> WebAutomation {
+    WebAutomation::Evaluate(format!(
+        "(() => {{
+            const entry = performance.getEntriesByType('navigation')[0];
+            if (!entry) return;
+            const script = document.createElement('script');
+            script.type = '{MARKER}';
+            script.textContent = JSON.stringify(entry.toJSON()).replace(/</g, '\\\\u003c');
+            document.documentElement.appendChild(script);
+        }})()"
+    ))
+}
+
+/// Removes the element left by [`capture`] from `html` and parses its timing.
+pub fn take(html: &mut String) -> Option<Timing> {
+    let start = html.rfind(&format!("<script type=\"{MARKER}\">"))?;
+    let end = start + html[start..].find("</script>")? + "</script>".len();
+    let element: String = html.drain(start..end).collect();
+    let json = element
+        .split_once('>')
+        .and_then(|(_, rest)| rest.strip_suffix("</script>"))?;
+    serde_json::from_str::<NavigationTiming>(json)
+        .ok()
+        .map(Timing::from)
+}
+
+impl From<NavigationTiming> for Timing {
+    fn from(t: NavigationTiming) -> Self {
+        // Phases that did not happen span zero, events that did not fire are at zero.
+        let span =
+            |from: f64, to: f64| (from > 0.0 && to > from).then(|| (to - from).round() as u64);
+        let since_start = |at: f64| (at > 0.0).then(|| (at - t.start_time).round() as u64);
+        Timing {
+            dns_ms: span(t.domain_lookup_start, t.domain_lookup_end),
+            connect_ms: span(t.connect_start, t.connect_end),
+            tls_ms: span(t.secure_connection_start, t.connect_end),
+            ttfb_ms: since_start(t.response_start),
+            download_ms: span(t.response_start, t.response_end),
+            dom_content_loaded_ms: since_start(t.dom_content_loaded_event_end),
+            load_ms: since_start(t.load_event_end),
+        }
+    }
+}